@@ -1,65 +1,619 @@
-use std::path::PathBuf;
-
-use crate::load::load_gfa;
-//use crate::utils::{self, GFAGraphLookups};
-//use crate::{gfa::graph::segments_subgraph, load::load_gfa_stdin};
-use crate::{gfa::graph::GFAdigraph};
-use crate::{gfa::gfa::into_digraph};
-use anyhow::{bail, Result};
-use petgraph::algo::{is_cyclic_directed,tarjan_scc};
-use petgraph::graph::{Graph, NodeIndex, UnGraph};
-use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-pub fn get_strong_terminal_nodes(
-    matches: &clap::ArgMatches,
-) -> Result<Option<(GFAdigraph, Vec<usize>)>> {
-    let gfa_file = matches.get_one::<PathBuf>("GFA").expect("Shit on it");
-    
-    let gfa = load_gfa(gfa_file).expect("k");
-    
-    /*
-    let gfa = match gfa_file {
-        Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::{into_digraph, GFAtk};
+use crate::gfa::gfa_string;
+use crate::gfa::graph::{reverse_index, GFAdigraph, OrientedSegment};
+use crate::gfa::names::SegmentNames;
+use crate::gfa::optfields::OptionalTags;
+use crate::gfa::topology::GFATopology;
+use crate::load::{load_gfa_topology, load_gfatk};
+use crate::logging::Stage;
+use crate::utils::format_usize_to_kb;
+use anyhow::{Context, Result};
+use bstr::ByteSlice;
+use gfa::gfa::{Link, Orientation, Segment, GFA};
+use gfa::optfields::{OptField, OptFieldVal};
+use log::info;
+use petgraph::graph::NodeIndex;
+use petgraph::visit::EdgeRef;
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+
+/// A strongly connected component of a GFA, as reported by `gfatk SSC`.
+#[derive(Clone, Debug, Serialize)]
+pub struct SCCRecord {
+    /// Arbitrary index of the component.
+    pub index: usize,
+    /// Names of the segments in the component.
+    pub segments: Vec<String>,
+    /// ID's of the segments in the component (see [`SegmentNames`]).
+    #[serde(skip)]
+    pub segment_ids: Vec<usize>,
+    /// The number of segments in the component.
+    pub node_count: usize,
+    /// The number of (oriented) links within the component.
+    pub edge_count: usize,
+    /// Total sequence length of all the segments.
+    pub total_sequence_length: usize,
+    /// Oriented segments outside of the component with a link into it.
+    pub entries: Vec<String>,
+    /// Oriented segments outside of the component reached by a link out of it.
+    pub exits: Vec<String>,
+    /// Whether the component is a single segment linked to itself.
+    pub is_self_loop: bool,
+    /// Paths (`P` lines) and haplotypes of walks (`W` lines) which traverse
+    /// the component.
+    pub haplotypes: Vec<String>,
+}
+
+/// A vector of `SCCRecord`.
+pub struct SCCRecords(pub Vec<SCCRecord>);
+
+impl SCCRecords {
+    /// Compute an [`SCCRecord`] for each of the strongly connected components
+    /// `sccs` of the GFA with at least `min_size` segments.
+    ///
+    /// `sccs` should hold one component of each mirror pair (see
+    /// [`GFAdigraph::strongly_connected_components`]); entries and exits are
+    /// given on the strand of that component.
+    pub fn from_graph(gfa: &GFATopology, sccs: &[Vec<NodeIndex>], min_size: usize) -> Self {
+        let gfa_graph = &gfa.graph;
+        let oriented = |segments: Vec<OrientedSegment>| -> Vec<String> {
+            segments
+                .into_iter()
+                .map(|s| gfa.names.oriented(s))
+                .collect()
+        };
+
+        let haplotypes = Haplotypes::new(gfa);
+        let mut records = Vec::new();
+
+        for scc in sccs {
+            let segments = gfa_graph.segment_ids(scc);
+            if segments.len() < min_size {
+                continue;
+            }
+
+            let members: HashSet<NodeIndex> = scc.iter().copied().collect();
+            let edge_count = scc
+                .iter()
+                .flat_map(|n| gfa_graph.0.edges(*n))
+                .filter(|e| members.contains(&e.target()))
+                .count();
+            let (entries, exits) = gfa_graph.boundary(scc);
+
+            records.push(SCCRecord {
+                index: records.len(),
+                node_count: segments.len(),
+                edge_count,
+                total_sequence_length: segments.iter().map(|s| gfa.lengths[*s]).sum(),
+                entries: oriented(entries),
+                exits: oriented(exits),
+                is_self_loop: segments.len() == 1 && edge_count > 0,
+                haplotypes: haplotypes.visiting(&segments),
+                segments: segments
+                    .iter()
+                    .map(|s| gfa.names.name(*s).to_string())
+                    .collect(),
+                segment_ids: segments,
+            });
+        }
+
+        Self(records)
+    }
+
+    /// Print tabular form of [`SCCRecords`] to STDOUT.
+    pub fn print_tabular(&self) {
+        let headers = [
+            "scc_index",
+            "segments",
+            "node_count",
+            "edge_count",
+            "total_seq_len",
+            "entries",
+            "exits",
+            "is_self_loop",
+            "haplotypes",
+        ];
+        println!("{}", headers.join("\t"));
+
+        for SCCRecord {
+            index,
+            segments,
+            node_count,
+            edge_count,
+            total_sequence_length,
+            entries,
+            exits,
+            is_self_loop,
+            haplotypes,
+            ..
+        } in &self.0
+        {
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                index,
+                join_ids(segments),
+                node_count,
+                edge_count,
+                total_sequence_length,
+                join_ids(entries),
+                join_ids(exits),
+                is_self_loop,
+                join_ids(haplotypes)
+            );
+        }
+    }
+
+    /// Print [`SCCRecords`] as a JSON array to STDOUT.
+    pub fn print_json(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.0)
+            .context("Could not serialise strongly connected components to JSON")?;
+        println!("{}", json);
+        Ok(())
+    }
+
+    /// Print a BED line for each segment in each of the [`SCCRecords`] to STDOUT.
+    ///
+    /// Coordinates are on the segment itself, and the name column is the component index.
+    pub fn print_bed(&self, gfa: &GFATopology) {
+        for record in &self.0 {
+            for segment in &record.segment_ids {
+                println!(
+                    "{}\t0\t{}\tscc_{}",
+                    gfa.names.name(*segment),
+                    gfa.lengths[*segment],
+                    record.index
+                );
+            }
+        }
+    }
+
+    /// Write each of the [`SCCRecords`] to its own GFA file in `outdir`, along
+    /// with a `manifest.tsv` describing each file.
+    ///
+    /// Each subgraph is padded with the segments up to `context` links away
+    /// from the component, in either direction. As the subgraphs need the
+    /// segment sequences, `gfa` is the full GFA; segments are matched to the
+    /// records by name.
+    pub fn write_subgraphs(
+        &self,
+        gfa: &GFAtk,
+        outdir: &Path,
+        context: i32,
+        coverage_tags: &CoverageTags,
+    ) -> Result<()> {
+        let (graph_indices, gfa_graph) = into_digraph(&gfa.0, coverage_tags)?;
+
+        fs::create_dir_all(outdir)
+            .with_context(|| format!("Could not create output directory {:?}", outdir))?;
+
+        let manifest_path = outdir.join("manifest.tsv");
+        let mut manifest = BufWriter::new(
+            File::create(&manifest_path)
+                .with_context(|| format!("Could not create {:?}", manifest_path))?,
+        );
+        writeln!(
+            manifest,
+            "scc_index\tfile\tsegments\tcontext_segments\ttotal_seq_len"
+        )?;
+
+        for record in &self.0 {
+            let segment_ids = record
+                .segments
+                .iter()
+                .map(|s| gfa.1.try_id(s))
+                .collect::<Result<Vec<usize>>>()?;
+            let start = segment_ids
+                .iter()
+                .map(|s| graph_indices[s])
+                .collect::<Vec<NodeIndex>>();
+            let keep = if context > 0 {
+                gfa_graph.recursive_search(&gfa.1, context, start)
+            } else {
+                segment_ids.clone()
+            };
+            let context_segments: Vec<String> = keep
+                .iter()
+                .filter(|s| !segment_ids.contains(s))
+                .map(|s| gfa.1.name(*s).to_string())
+                .collect();
+
+            let file_name = format!("scc_{}.gfa", record.index);
+            let gfa_path = outdir.join(&file_name);
+            fs::write(&gfa_path, gfa.subgraph(keep).to_gfa_string())
+                .with_context(|| format!("Could not write {:?}", gfa_path))?;
+
+            writeln!(
+                manifest,
+                "{}\t{}\t{}\t{}\t{}",
+                record.index,
+                file_name,
+                join_ids(&record.segments),
+                join_ids(&context_segments),
+                record.total_sequence_length
+            )?;
+        }
+        manifest.flush()?;
+
+        info!(
+            "Wrote {} strongly connected components to {:?}.",
+            self.0.len(),
+            outdir
+        );
+
+        Ok(())
+    }
+}
+
+/// The condensation of a GFA, where each strongly connected component is
+/// collapsed to a single segment.
+///
+/// The condensed segments are numbered from 1, in the order of the components
+/// they replace. A component's mirror image on the opposite strand is the
+/// reverse orientation of the same condensed segment.
+pub struct Condensation {
+    /// The segment ID's in each condensed segment.
+    pub components: Vec<Vec<usize>>,
+    /// The total sequence length of each condensed segment.
+    pub lengths: Vec<usize>,
+    /// The links between condensed segments, with their summed edge coverage if
+    /// every collapsed link had one. Each link is stored once.
+    pub links: BTreeMap<(OrientedSegment, OrientedSegment), Option<i64>>,
+}
+
+impl Condensation {
+    /// Collapse each of the strongly connected components `sccs` of `gfa_graph`.
+    ///
+    /// `sccs` must hold exactly one component of each mirror pair, as returned by
+    /// [`GFAdigraph::strongly_connected_components`].
+    pub fn from_graph(gfa: &GFATopology, sccs: &[Vec<NodeIndex>]) -> Self {
+        let gfa_graph = &gfa.graph;
+        let graph = &gfa_graph.0;
+
+        // the condensed (oriented) segment of each node
+        let mut condensed: HashMap<NodeIndex, OrientedSegment> = HashMap::new();
+        let mut components = Vec::new();
+        let mut lengths = Vec::new();
+
+        for (index, scc) in sccs.iter().enumerate() {
+            let segments = gfa_graph.segment_ids(scc);
+            lengths.push(segments.iter().map(|s| gfa.lengths[*s]).sum());
+            components.push(segments);
+
+            let forward = OrientedSegment {
+                seg_id: index + 1,
+                orientation: Orientation::Forward,
+            };
+            for node in scc {
+                condensed.insert(*node, forward);
+            }
+        }
+        // the mirror images of the components
+        for node in graph.node_indices() {
+            if !condensed.contains_key(&node) {
+                let mirror = condensed[&reverse_index(node)].flip();
+                condensed.insert(node, mirror);
+            }
+        }
+
+        let mut links: BTreeMap<(OrientedSegment, OrientedSegment), Option<i64>> = BTreeMap::new();
+        for edge in graph.edge_references() {
+            let (source, target) = (edge.source(), edge.target());
+            // this is the reverse complement of a link we count elsewhere
+            if (reverse_index(target), reverse_index(source)) < (source, target) {
+                continue;
+            }
+            let from = condensed[&source];
+            let to = condensed[&target];
+            if from == to {
+                continue;
+            }
+            // store each condensed link in one orientation only
+            let key = (from, to).min((to.flip(), from.flip()));
+            let coverage = links.entry(key).or_insert(Some(0));
+            *coverage = coverage.zip(*edge.weight()).map(|(a, b)| a + b);
+        }
+
+        Self {
+            components,
+            lengths,
+            links,
+        }
+    }
+
+    /// Convert the condensation to a GFA.
+    ///
+    /// Condensed segments have no sequence (`*`), their total sequence length as
+    /// an `LN:i` tag, and their segment names as an `sg:Z` tag. Links have no
+    /// overlap (`*`), and an `ec:i` tag if the coverage is known.
+    pub fn to_gfa(&self, names: &SegmentNames) -> GFA<usize, OptionalTags> {
+        let mut gfa = GFA::new();
+
+        for (index, (segments, length)) in self.components.iter().zip(&self.lengths).enumerate() {
+            gfa.segments.push(Segment {
+                name: index + 1,
+                sequence: b"*".to_vec(),
+                optional: vec![
+                    OptField::new(b"LN", OptFieldVal::Int(*length as i64)),
+                    OptField::new(b"sg", OptFieldVal::Z(names.join(segments).into_bytes())),
+                ]
+                .into(),
+            });
+        }
+
+        for ((from, to), coverage) in &self.links {
+            gfa.links.push(Link {
+                from_segment: from.seg_id,
+                from_orient: from.orientation,
+                to_segment: to.seg_id,
+                to_orient: to.orientation,
+                overlap: b"*".to_vec(),
+                optional: coverage
+                    .map(|ec| vec![OptField::new(b"ec", OptFieldVal::Int(ec))].into())
+                    .unwrap_or_default(),
+            });
+        }
+
+        gfa
+    }
+
+    /// Print a DOT language representation of the condensation to STDOUT.
+    ///
+    /// Each condensed segment is labelled with its index, segment count and total sequence length.
+    pub fn print_dot(&self) {
+        static INDENT: &str = "    ";
+
+        println!("digraph condensation {{");
+        for (index, (segments, length)) in self.components.iter().zip(&self.lengths).enumerate() {
+            println!(
+                "{}{} [ label = <<FONT POINT-SIZE=\'20\'>{}</FONT><br/><FONT POINT-SIZE=\'10\'>N: {}</FONT><br/><FONT POINT-SIZE=\'10\'>L: {}</FONT>> ];",
+                INDENT,
+                index + 1,
+                index + 1,
+                segments.len(),
+                format_usize_to_kb(*length)
+            );
+        }
+        for ((from, to), coverage) in &self.links {
+            let arrowhead_shape = match to.orientation {
+                Orientation::Forward => "ornormal",
+                Orientation::Backward => "olnormal",
+            };
+            println!(
+                "{}{} -> {} [ label = \"  {}  \" taillabel = \"  {}  \" headlabel = \"  {}  \" arrowhead = \"{}\" ];",
+                INDENT,
+                from.seg_id,
+                to.seg_id,
+                coverage.map(|c| c.to_string()).unwrap_or_default(),
+                from.orientation,
+                to.orientation,
+                arrowhead_shape
+            );
+        }
+        println!("}}");
+    }
+}
+
+/// The paths (by name) and walks (by PanSN haplotype, e.g. `HG002#1#chr1`)
+/// of a GFA, and the indices of those which visit each segment.
+///
+/// Built once, so each component only looks up its own segments rather than
+/// scanning every step of every path and walk.
+struct Haplotypes {
+    names: Vec<String>,
+    by_segment: HashMap<usize, Vec<usize>>,
+}
+
+impl Haplotypes {
+    fn new(gfa: &GFATopology) -> Self {
+        let paths = gfa
+            .paths
+            .iter()
+            .map(|(name, steps)| (name.as_bstr().to_string(), steps));
+        let walks = gfa
+            .walks_jumps
+            .walks
+            .iter()
+            .map(|w| (w.haplotype(), &w.steps));
+
+        let mut names = Vec::new();
+        let mut indices = HashMap::new();
+        let mut by_segment: HashMap<usize, Vec<usize>> = HashMap::new();
+        for (haplotype, steps) in paths.chain(walks) {
+            // a haplotype may be split over several walks
+            let index = *indices.entry(haplotype).or_insert_with_key(|h| {
+                names.push(h.clone());
+                names.len() - 1
+            });
+            for (segment, _) in steps {
+                let visits = by_segment.entry(*segment).or_default();
+                if visits.last() != Some(&index) {
+                    visits.push(index);
                 }
-                None => bail!("Could not read file."),
             }
         }
-    };
-    */
 
-    // load gfa into graph structure
-    let (node_to_index, gfa_graph) = into_digraph(gfa)?;
-    eprintln!("[+]\tFinished reading GFA into a directed graph.");
-    let SSCs = tarjan_scc(&gfa_graph.0);
+        Self { names, by_segment }
+    }
+
+    /// The haplotypes which visit any of these segments, in the order they
+    /// are in the GFA.
+    fn visiting(&self, segments: &[usize]) -> Vec<String> {
+        let indices: BTreeSet<usize> = segments
+            .iter()
+            .filter_map(|s| self.by_segment.get(s))
+            .flatten()
+            .copied()
+            .collect();
+        indices.into_iter().map(|i| self.names[i].clone()).collect()
+    }
+}
 
-    eprintln!("[+]\tFinished reading GFA into a directed graph.");
-    let index_to_node = invert(node_to_index);
+/// Join (oriented) segment ID's with a comma, or "-" if there are none.
+fn join_ids<T: ToString>(ids: &[T]) -> String {
+    if ids.is_empty() {
+        return "-".to_string();
+    }
+    ids.iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Find the strongly connected components of a GFA, and print the oriented segments
+/// entering and leaving each component of at least `--size` segments.
+///
+/// With `--format`, a full report of each component is printed as TSV, JSON or BED.
+/// With `--condense`, the condensation of the GFA (every component collapsed to a
+/// single segment) is printed as GFA or DOT instead. With `--outdir`, each component
+/// is written to its own GFA (optionally with `--context` links of flanking segments),
+/// alongside a manifest.
+///
+/// Only the topology of the GFA is read (see [`GFATopology`]), so sequences are
+/// never held in memory, except with `--outdir` where the full GFA is read again
+/// to write out the subgraphs.
+///
+/// For example:
+/// ```bash
+/// gfatk SSC in.gfa -s 5
+/// gfatk SSC in.gfa -s 5 -f json
+/// gfatk SSC in.gfa -c dot | dot -Tsvg > condensed.svg
+/// gfatk SSC in.gfa -s 5 -o tangles --context 1
+/// gfatk SSC in.gfa -s 5 --threads 8
+/// ```
+pub fn get_strong_terminal_nodes(
+    matches: &clap::ArgMatches,
+) -> Result<Option<(GFAdigraph, Vec<usize>)>> {
+    let gfa_file = matches
+        .get_one::<PathBuf>("GFA")
+        .ok_or_else(|| GfatkError::Input("No input GFA. Run `gfatk SSC -h` for help.".into()))?;
+
+    // stream the topology straight into a graph structure
+    let coverage_tags = CoverageTags::from_matches(matches)?;
+    let gfa = load_gfa_topology(gfa_file, &coverage_tags)?;
+    let threads = *matches
+        .get_one::<usize>("threads")
+        .expect("defaulted by clap");
+    let stage = Stage::start("Finding strongly connected components");
+    let sccs = gfa.graph.par_strongly_connected_components(threads)?;
+    info!("Found {} strongly connected components.", sccs.len());
+    stage.finish();
+
+    match matches.get_one::<String>("condense").map(|c| c.as_str()) {
+        Some("gfa") => {
+            let condensation = Condensation::from_graph(&gfa, &sccs);
+            print!("{}", gfa_string(&condensation.to_gfa(&gfa.names)));
+            return Ok(None);
+        }
+        Some("dot") => {
+            Condensation::from_graph(&gfa, &sccs).print_dot();
+            return Ok(None);
+        }
+        _ => (),
+    }
 
     let min_length = *matches.get_one::<usize>("Size").expect("defaulted by clap");
+    let records = SCCRecords::from_graph(&gfa, &sccs, min_length);
 
-    for SCC in SSCs.iter(){
-      if SCC.len() >= min_length {
-        let mut nodes: Vec<_> = SCC.iter().map(|x| index_to_node[x]).collect::<Vec<_>>();
-        nodes.sort();
-        println!("{} {}",nodes.first().unwrap()-1,nodes.last().unwrap()+1);
-      }
+    if let Some(outdir) = matches.get_one::<PathBuf>("outdir") {
+        let context = *matches
+            .get_one::<i32>("context")
+            .expect("defaulted by clap");
+        records.write_subgraphs(&load_gfatk(gfa_file)?, outdir, context, &coverage_tags)?;
+        return Ok(None);
+    }
+
+    match matches.get_one::<String>("format").map(|f| f.as_str()) {
+        Some("tsv") => records.print_tabular(),
+        Some("json") => records.print_json()?,
+        Some("bed") => records.print_bed(&gfa),
+        _ => {
+            for SCCRecord { entries, exits, .. } in &records.0 {
+                println!("{} {}", join_ids(entries), join_ids(exits));
+            }
+        }
     }
 
     Ok(None)
 }
 
-fn invert(map: HashMap<usize,NodeIndex>) -> HashMap<NodeIndex,usize> {
-    let mut invert = HashMap::new();
-    for (key, value) in map.into_iter() {
-        invert.insert(value, key);
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_scc_records() {
+        let gfa = load_gfa_topology("./tests/test_scc.gfa", &CoverageTags::default()).unwrap();
+
+        let sccs = gfa.graph.strongly_connected_components();
+        let records = SCCRecords::from_graph(&gfa, &sccs, 1).0;
+
+        let tangle = records.iter().find(|r| r.segments == ["2", "3"]).unwrap();
+        assert_eq!(tangle.node_count, 2);
+        assert_eq!(tangle.edge_count, 2);
+        assert_eq!(tangle.total_sequence_length, 12);
+        assert_eq!(tangle.entries, ["1+"]);
+        assert_eq!(tangle.exits, ["4+"]);
+        assert!(!tangle.is_self_loop);
+
+        let self_loop = records.iter().find(|r| r.segments == ["5"]).unwrap();
+        assert!(self_loop.is_self_loop);
+        assert!(self_loop.entries.is_empty() && self_loop.exits.is_empty());
+
+        // mirror images on the opposite strand are not reported again
+        assert_eq!(records.len(), 4);
+    }
+
+    #[test]
+    fn test_scc_haplotypes() {
+        let gfa = load_gfa_topology("./tests/test_walks.gfa", &CoverageTags::default()).unwrap();
+
+        let sccs = gfa.graph.strongly_connected_components();
+        let records = SCCRecords::from_graph(&gfa, &sccs, 1).0;
+
+        let tangle = records.iter().find(|r| r.segments == ["s2", "s3"]).unwrap();
+        assert_eq!(
+            tangle.haplotypes,
+            ["HG002#1#chr1", "HG002#2#chr1", "HG003#1#chr1"]
+        );
+
+        let self_loop = records.iter().find(|r| r.segments == ["s5"]).unwrap();
+        assert_eq!(self_loop.haplotypes, ["HG003#1#chr2"]);
+    }
+
+    #[test]
+    fn test_condensation() {
+        let gfa = load_gfa_topology("./tests/test_scc.gfa", &CoverageTags::default()).unwrap();
+
+        let sccs = gfa.graph.strongly_connected_components();
+        let condensation = Condensation::from_graph(&gfa, &sccs);
+
+        let components: Vec<String> = condensation
+            .components
+            .iter()
+            .map(|c| gfa.names.join(c))
+            .collect();
+        assert_eq!(components, ["1", "2,3", "4", "5"]);
+        assert_eq!(condensation.lengths, vec![6, 12, 6, 6]);
+
+        // 1+ -> (2,3)+ -> 4+; the self-loop on 5 and the cycle are collapsed
+        let links: Vec<String> = condensation
+            .links
+            .iter()
+            .map(|((from, to), ec)| format!("{} {} {:?}", from, to, ec))
+            .collect();
+        assert_eq!(links, vec!["1+ 2+ Some(1)", "2+ 3+ Some(1)"]);
+
+        // the condensation is a valid GFA which can be read back in
+        let condensed = condensation.to_gfa(&gfa.names);
+        let (_, condensed_graph) = into_digraph(&condensed, &CoverageTags::default()).unwrap();
+        assert_eq!(condensed_graph.node_count(), 4);
+        assert_eq!(condensed_graph.edge_count(), 4);
     }
-    return invert;
 }
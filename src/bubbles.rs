@@ -0,0 +1,233 @@
+use std::path::PathBuf;
+
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::graph::Superbubble;
+use crate::gfa::topology::GFATopology;
+use crate::load::load_gfa_topology;
+use crate::logging::Stage;
+use anyhow::Result;
+use log::info;
+use petgraph::graph::NodeIndex;
+
+/// A bubble or superbubble of a GFA, as reported by `gfatk bubbles`.
+#[derive(Clone, Debug)]
+pub struct BubbleRecord {
+    /// Arbitrary index of the bubble.
+    pub index: usize,
+    /// Whether every allele has at most one segment.
+    pub is_simple: bool,
+    /// The oriented segment the bubble opens with.
+    pub source: String,
+    /// The oriented segment the bubble closes with.
+    pub sink: String,
+    /// Names of the segments inside the bubble.
+    pub segments: Vec<String>,
+    /// ID's of the segments inside the bubble (see [`SegmentNames`](crate::gfa::names::SegmentNames)).
+    pub segment_ids: Vec<usize>,
+    /// The number of paths from the source to the sink.
+    pub allele_count: u64,
+    /// The oriented segments of each path from the source to the sink
+    /// (excluding both), up to the maximum number asked for.
+    pub alleles: Vec<Vec<String>>,
+    /// The total length of the segments of each allele.
+    pub allele_lengths: Vec<usize>,
+}
+
+/// A vector of `BubbleRecord`.
+pub struct BubbleRecords(pub Vec<BubbleRecord>);
+
+impl BubbleRecords {
+    /// Compute a [`BubbleRecord`] for each of the superbubbles of the GFA,
+    /// listing at most `max_alleles` of the paths through each one.
+    pub fn from_graph(gfa: &GFATopology, bubbles: &[Superbubble], max_alleles: usize) -> Self {
+        let gfa_graph = &gfa.graph;
+        let oriented = |node: NodeIndex| gfa.names.oriented(gfa_graph.oriented_segment(node));
+
+        let mut records = Vec::new();
+
+        for bubble in bubbles {
+            let segment_ids = gfa_graph.segment_ids(&bubble.inside);
+
+            let paths = gfa_graph.superbubble_paths(bubble, max_alleles);
+
+            records.push(BubbleRecord {
+                index: records.len(),
+                is_simple: gfa_graph.is_simple_bubble(bubble),
+                source: oriented(bubble.source),
+                sink: oriented(bubble.sink),
+                segments: segment_ids
+                    .iter()
+                    .map(|s| gfa.names.name(*s).to_string())
+                    .collect(),
+                segment_ids,
+                allele_count: gfa_graph.superbubble_path_count(bubble),
+                allele_lengths: paths
+                    .iter()
+                    .map(|path| {
+                        path.iter()
+                            .map(|n| gfa.lengths[gfa_graph.oriented_segment(*n).seg_id])
+                            .sum()
+                    })
+                    .collect(),
+                alleles: paths
+                    .iter()
+                    .map(|path| path.iter().map(|n| oriented(*n)).collect())
+                    .collect(),
+            });
+        }
+
+        Self(records)
+    }
+
+    /// Print tabular form of [`BubbleRecords`] to STDOUT.
+    ///
+    /// Alleles are separated by `;`, and an allele with no segments (a link
+    /// straight from the source to the sink) is given as `-`.
+    pub fn print_tabular(&self) {
+        let headers = [
+            "bubble_index",
+            "type",
+            "source",
+            "sink",
+            "segments",
+            "allele_count",
+            "alleles",
+            "allele_lengths",
+        ];
+        println!("{}", headers.join("\t"));
+
+        for record in &self.0 {
+            let alleles: Vec<String> = record.alleles.iter().map(|a| join(a)).collect();
+            println!(
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+                record.index,
+                if record.is_simple {
+                    "bubble"
+                } else {
+                    "superbubble"
+                },
+                record.source,
+                record.sink,
+                join(&record.segments),
+                record.allele_count,
+                alleles.join(";"),
+                join(&record.allele_lengths)
+            );
+        }
+    }
+
+    /// Print a BED line for each segment inside each of the [`BubbleRecords`] to STDOUT.
+    ///
+    /// Coordinates are on the segment itself, and the name column is the bubble index.
+    pub fn print_bed(&self, gfa: &GFATopology) {
+        for record in &self.0 {
+            for segment in &record.segment_ids {
+                println!(
+                    "{}\t0\t{}\tbubble_{}",
+                    gfa.names.name(*segment),
+                    gfa.lengths[*segment],
+                    record.index
+                );
+            }
+        }
+    }
+}
+
+/// Join with a comma, or "-" if there is nothing to join.
+fn join<T: ToString>(items: &[T]) -> String {
+    if items.is_empty() {
+        return "-".to_string();
+    }
+    items
+        .iter()
+        .map(|s| s.to_string())
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Find the bubbles and superbubbles of a GFA, and print the source, sink,
+/// segments and alleles of each one.
+///
+/// Only the topology of the GFA is read (see [`GFATopology`]). Bubbles are
+/// found on the oriented segments, so a bubble may hold both strands of a
+/// segment (e.g. an inversion).
+///
+/// For example:
+/// ```bash
+/// gfatk bubbles in.gfa
+/// gfatk bubbles in.gfa -f bed > bubbles.bed
+/// ```
+pub fn bubbles(matches: &clap::ArgMatches) -> Result<()> {
+    let gfa_file = matches.get_one::<PathBuf>("GFA").ok_or_else(|| {
+        GfatkError::Input("No input GFA. Run `gfatk bubbles -h` for help.".into())
+    })?;
+    let max_size = *matches
+        .get_one::<usize>("max_size")
+        .expect("defaulted by clap");
+    let max_alleles = *matches
+        .get_one::<usize>("max_alleles")
+        .expect("defaulted by clap");
+
+    let gfa = load_gfa_topology(gfa_file, &CoverageTags::from_matches(matches)?)?;
+
+    let stage = Stage::start("Finding bubbles");
+    let bubbles = gfa.graph.superbubbles(max_size);
+    info!("Found {} bubbles.", bubbles.len());
+    stage.finish();
+
+    let records = BubbleRecords::from_graph(&gfa, &bubbles, max_alleles);
+
+    match matches.get_one::<String>("format").map(|f| f.as_str()) {
+        Some("bed") => records.print_bed(&gfa),
+        _ => records.print_tabular(),
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_bubble_records() {
+        let gfa = load_gfa_topology("./tests/test_bubbles.gfa", &CoverageTags::default()).unwrap();
+
+        let bubbles = gfa.graph.superbubbles(100);
+        let records = BubbleRecords::from_graph(&gfa, &bubbles, 10).0;
+
+        let summary: Vec<(bool, &str, &str, u64)> = records
+            .iter()
+            .map(|r| {
+                (
+                    r.is_simple,
+                    r.source.as_str(),
+                    r.sink.as_str(),
+                    r.allele_count,
+                )
+            })
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                (true, "1+", "4+", 2),
+                (false, "4+", "9+", 3),
+                (true, "5+", "8+", 2),
+            ]
+        );
+
+        let superbubble = &records[1];
+        assert_eq!(superbubble.segments, ["5", "6", "7", "8"]);
+        assert_eq!(
+            superbubble.alleles,
+            vec![vec![], vec!["5+", "6+", "8+"], vec!["5+", "7-", "8+"]]
+        );
+        assert_eq!(superbubble.allele_lengths, [0, 12, 13]);
+
+        // a cycle is not a bubble
+        let gfa = load_gfa_topology("./tests/test_circular.gfa", &CoverageTags::default()).unwrap();
+        assert!(gfa.graph.superbubbles(100).is_empty());
+    }
+}
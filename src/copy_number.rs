@@ -0,0 +1,92 @@
+use std::path::PathBuf;
+
+use crate::error::GfatkError;
+use crate::gfa::copy_number::CopyNumbers;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::{into_digraph, GFAtk};
+use crate::load::{load_gfatk, load_gfatk_stdin};
+use crate::utils;
+use anyhow::{bail, Result};
+use log::{info, warn};
+
+/// Print tabular form of [`CopyNumbers`] to STDOUT, with the original segment names.
+fn print_tabular(gfa: &GFAtk, copy_numbers: &CopyNumbers) {
+    let headers = [
+        "segment",
+        "component",
+        "coverage",
+        "baseline",
+        "copy_number",
+        "confidence",
+    ];
+    println!("{}", headers.join("\t"));
+
+    for (id, cn) in &copy_numbers.0 {
+        println!(
+            "{}\t{}\t{}\t{}\t{}\t{:.2}",
+            gfa.1.name(*id),
+            cn.component,
+            cn.coverage,
+            cn.baseline,
+            cn.copy_number,
+            cn.confidence
+        );
+    }
+}
+
+/// Estimate the copy number of each segment of a GFA from its coverage.
+///
+/// A baseline (single copy) coverage is fitted to each subgraph from its long
+/// segments, and each segment is given the nearest whole number of copies of
+/// it. By default the GFA is printed with a `CN:i` tag on each segment.
+///
+/// For example:
+/// ```bash
+/// gfatk copy-number in.gfa > out.gfa
+/// gfatk copy-number in.gfa -f tsv
+/// ```
+pub fn copy_number(matches: &clap::ArgMatches) -> Result<()> {
+    let gfa_file = matches.get_one::<PathBuf>("GFA");
+    let min_length = *matches
+        .get_one::<usize>("min-length")
+        .expect("defaulted by clap");
+
+    let gfa: GFAtk = match gfa_file {
+        Some(f) => {
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
+            }
+            load_gfatk(f)?
+        }
+        None => match utils::is_stdin() {
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk copy-number -h` for help.".into()
+            )),
+        },
+    };
+
+    let coverage_tags = CoverageTags::from_matches(matches)?;
+    let (_, gfa_graph) = into_digraph(&gfa.0, &coverage_tags)?;
+    let copy_numbers = gfa.copy_numbers(
+        &gfa_graph.weakly_connected_components(),
+        min_length,
+        &coverage_tags,
+    );
+
+    let missing = gfa.0.segments.len() - copy_numbers.0.len();
+    if missing > 0 {
+        warn!("{} segments have no coverage, so no copy number.", missing);
+    }
+    info!(
+        "Estimated the copy number of {} segments.",
+        copy_numbers.0.len()
+    );
+
+    match matches.get_one::<String>("format").map(|f| f.as_str()) {
+        Some("tsv") => print_tabular(&gfa, &copy_numbers),
+        _ => print!("{}", gfa.with_copy_numbers(&copy_numbers).to_gfa_string()),
+    }
+
+    Ok(())
+}
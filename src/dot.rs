@@ -3,8 +3,10 @@
 
 use std::path::PathBuf;
 
-use crate::gfa::gfa::GFAtk;
-use crate::load::{load_gfa, load_gfa_stdin};
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::{into_digraph, GFAtk};
+use crate::load::{load_gfatk, load_gfatk_stdin};
 use crate::utils;
 use anyhow::{bail, Result};
 
@@ -19,27 +21,23 @@ pub fn dot(matches: &clap::ArgMatches) -> Result<()> {
 
     let gfa: GFAtk = match gfa_file {
         Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
-                }
-                None => bail!("Could not read file."),
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => GFAtk(load_gfa_stdin(std::io::stdin().lock())?),
-            false => bail!("No input from STDIN. Run `gfatk dot -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk dot -h` for help.".into()
+            )),
         },
     };
 
-    let (_, gfa_graph) = gfa.into_digraph()?;
+    let coverage_tags = CoverageTags::from_matches(matches)?;
+    let (_, gfa_graph) = into_digraph(&gfa.0, &coverage_tags)?;
 
-    gfa_graph.dot(gfa)?;
+    gfa_graph.dot(gfa, &coverage_tags)?;
 
     Ok(())
 }
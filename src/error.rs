@@ -0,0 +1,107 @@
+use thiserror::Error;
+
+/// The classes of error `gfatk` can fail with.
+///
+/// Functions still return [`anyhow::Result`], so context can be added as the
+/// error is passed up, but the root cause is one of these where it is known.
+/// Each class exits `gfatk` with its own code (see [`GfatkError::exit_code`]),
+/// so scripts can tell, for example, a malformed GFA from a missing tag.
+#[derive(Debug, Error)]
+pub enum GfatkError {
+    /// The input is not usable, e.g. it is not a GFA, or there is none.
+    #[error("{0}")]
+    Input(String),
+    /// Reading or writing a file failed.
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// A line of the GFA could not be parsed.
+    #[error("Line {line} of the GFA could not be parsed: {message}")]
+    Parse {
+        /// The 1-based line number.
+        line: usize,
+        /// What was wrong with the line.
+        message: String,
+    },
+    /// A segment is used (e.g. by a link or path) but is not in the GFA.
+    #[error("Segment {0} does not exist in the GFA.")]
+    MissingSegment(String),
+    /// Two segments are not linked, but are adjacent in a path.
+    #[error("There is no link {0} in the GFA.")]
+    MissingLink(String),
+    /// A CIGAR string which could not be parsed or is not supported.
+    #[error("Invalid CIGAR string {cigar}: {message}")]
+    InvalidCigar {
+        /// The CIGAR string.
+        cigar: String,
+        /// What was wrong with it.
+        message: String,
+    },
+    /// An optional field (tag) is missing, or has the wrong type.
+    #[error("Missing {tag} tag: {message}")]
+    MissingTag {
+        /// The two letter tag, e.g. `ec`.
+        tag: String,
+        /// Where it is missing from.
+        message: String,
+    },
+    /// `gfatk validate` found errors in the GFA.
+    #[error("The GFA has {errors} errors and {warnings} warnings.")]
+    Invalid {
+        /// The number of problems which are errors.
+        errors: usize,
+        /// The number of problems which are only warnings.
+        warnings: usize,
+    },
+}
+
+impl GfatkError {
+    /// The exit code for this class of error.
+    ///
+    /// 1 is left for errors of no particular class, and 2 is shared with
+    /// `clap`'s errors for bad command line arguments.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            GfatkError::Input(_) => 2,
+            GfatkError::Io(_) => 3,
+            GfatkError::Parse { .. } => 4,
+            GfatkError::MissingSegment(_) => 5,
+            GfatkError::MissingLink(_) => 6,
+            GfatkError::InvalidCigar { .. } => 7,
+            GfatkError::MissingTag { .. } => 8,
+            GfatkError::Invalid { .. } => 9,
+        }
+    }
+}
+
+/// The exit code for an error: that of the first [`GfatkError`] in its chain
+/// of causes, that of [`GfatkError::Io`] for a bare I/O error, or 1 otherwise.
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    let code = |cause: &(dyn std::error::Error + 'static)| {
+        cause
+            .downcast_ref::<GfatkError>()
+            .map(GfatkError::exit_code)
+            .or_else(|| cause.is::<std::io::Error>().then_some(3))
+    };
+    error.chain().find_map(code).unwrap_or(1)
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_exit_code() {
+        let error: anyhow::Result<()> = Err(GfatkError::MissingSegment("utg1".into()))
+            .context("Could not find a path through the GFA");
+        let error = error.unwrap_err();
+
+        assert_eq!(exit_code(&error), 5);
+        assert_eq!(
+            format!("{:#}", error),
+            "Could not find a path through the GFA: Segment utg1 does not exist in the GFA."
+        );
+        assert_eq!(exit_code(&anyhow::anyhow!("Something else.")), 1);
+    }
+}
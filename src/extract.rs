@@ -1,10 +1,11 @@
 use std::path::PathBuf;
 
-use crate::gfa::gfa::GFAtk;
-use crate::load::{load_gfa, load_gfa_stdin};
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::{into_digraph, GFAtk};
+use crate::load::{load_gfatk, load_gfatk_stdin};
 use crate::utils;
 use anyhow::{bail, Context, Result};
-use petgraph::graph::NodeIndex;
 
 /// Supply a sequence/segment ID from the GFA, and extract the GFA with all nodes connected to the input node.
 ///
@@ -16,9 +17,8 @@ pub fn extract(matches: &clap::ArgMatches) -> Result<()> {
     // read in path and parse gfa
     let gfa_file = matches.get_one::<PathBuf>("GFA");
     let sequence_ids = matches
-        .get_many::<usize>("sequence-ids")
+        .get_many::<String>("sequence-ids")
         .expect("errored by clap")
-        .copied()
         .collect::<Vec<_>>();
     let iterations = *matches
         .get_one::<i32>("iterations")
@@ -26,37 +26,30 @@ pub fn extract(matches: &clap::ArgMatches) -> Result<()> {
 
     let gfa: GFAtk = match gfa_file {
         Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
-                }
-                None => bail!("Could not read file."),
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => GFAtk(load_gfa_stdin(std::io::stdin().lock())?),
-            false => bail!("No input from STDIN. Run `gfatk extract -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk extract -h` for help.".into()
+            )),
         },
     };
 
-    let (graph_indices, gfa_graph) = gfa.into_ungraph()?;
+    let (graph_indices, gfa_graph) = into_digraph(&gfa.0, &CoverageTags::from_matches(matches)?)?;
 
     // get the node index of the target sequence ID.
     let target_indices = sequence_ids
         .iter()
-        .map(|e| graph_indices.seg_id_to_node_index(*e))
-        .collect::<Result<Vec<NodeIndex>>>();
+        .map(|e| gfa.1.id(e.as_bytes()))
+        .map(|e| e.and_then(|id| graph_indices.get(&id).copied()))
+        .collect::<Option<Vec<_>>>()
+        .context("One of your input segment ID's does not exist in the graph.")?;
 
-    let target_indices =
-        target_indices.context("One of your input segment ID's does not exist in the graph.")?;
-
-    let sequences_to_keep =
-        gfa_graph.recursive_search(sequence_ids, iterations, target_indices, graph_indices)?;
+    let sequences_to_keep = gfa_graph.recursive_search(&gfa.1, iterations, target_indices);
 
     gfa.print_extract(sequences_to_keep);
 
@@ -1,5 +1,4 @@
-use crate::gfa::gfa_string;
-use crate::{gfa::graph::segments_subgraph, stats, stats::GenomeType};
+use crate::{stats, stats::GenomeType};
 use anyhow::{Context, Result};
 
 /// Using a combination of GC% of the segments, relative coverage of the
@@ -13,9 +12,9 @@ pub fn extract_chloro(matches: &clap::ArgMatches, genome_type: GenomeType) -> Re
     let result = stats::stats(matches, genome_type)?
         .context("Should never reach here with `stats::GenomeType::Chloroplast`")?;
 
-    let subgraph = segments_subgraph(&result.0 .0, result.1);
+    let subgraph = result.0.subgraph(result.1);
 
-    println!("{}", gfa_string(&subgraph));
+    println!("{}", subgraph.to_gfa_string());
 
     Ok(())
 }
@@ -1,9 +1,11 @@
 use std::path::PathBuf;
 
+use crate::error::GfatkError;
 use crate::gfa::gfa::GFAtk;
-use crate::load::{load_gfa, load_gfa_stdin};
+use crate::load::{load_gfa_index, load_gfatk, load_gfatk_stdin};
 use crate::utils;
 use anyhow::{bail, Result};
+use bstr::ByteSlice;
 
 /// Print a fasta representation of the sequences in a GFA.
 ///
@@ -11,27 +13,41 @@ use anyhow::{bail, Result};
 /// ```bash
 /// gfatk fasta in.gfa > out.fasta
 /// ```
+///
+/// Or only some segments, read through the sequence index (see `gfatk index`):
+/// ```bash
+/// gfatk fasta --segments utg1,utg2 in.gfa > out.fasta
+/// ```
 pub fn fasta(matches: &clap::ArgMatches) -> Result<()> {
     // read in path and parse gfa
     let gfa_file = matches.get_one::<PathBuf>("GFA");
 
+    if let Some(segments) = matches.get_many::<String>("segments") {
+        // clap makes sure there is a GFA path with --segments
+        let gfa_file = gfa_file.expect("--segments requires a GFA");
+        if !utils::is_gfa_path(gfa_file) {
+            bail!(GfatkError::Input("Input is not a GFA.".into()))
+        }
+        let index = load_gfa_index(gfa_file)?;
+        for segment in segments {
+            let sequence = index.sequence(segment.as_bytes())?;
+            println!(">{}\n{}", segment, sequence.as_bstr());
+        }
+        return Ok(());
+    }
+
     let gfa: GFAtk = match gfa_file {
         Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
-                }
-                None => bail!("Could not read file."),
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => GFAtk(load_gfa_stdin(std::io::stdin().lock())?),
-            false => bail!("No input from STDIN. Run `gfatk extract -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk extract -h` for help.".into()
+            )),
         },
     };
 
@@ -0,0 +1,236 @@
+use crate::error::GfatkError;
+use std::fmt;
+
+/// A single CIGAR operation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CigarOp {
+    /// `M`, an alignment match (either a sequence match or mismatch).
+    Match,
+    /// `I`, bases in the `to` segment which are not in the `from` segment.
+    Insertion,
+    /// `D`, bases in the `from` segment which are not in the `to` segment.
+    Deletion,
+    /// `N`, skipped bases in the `from` segment.
+    Skip,
+    /// `S`, soft clipped bases.
+    SoftClip,
+    /// `H`, hard clipped bases.
+    HardClip,
+    /// `P`, padding.
+    Padding,
+    /// `=`, a sequence match.
+    SequenceMatch,
+    /// `X`, a sequence mismatch.
+    SequenceMismatch,
+}
+
+impl CigarOp {
+    /// Parse an operation from its character.
+    fn from_byte(byte: u8) -> Option<Self> {
+        Some(match byte {
+            b'M' => CigarOp::Match,
+            b'I' => CigarOp::Insertion,
+            b'D' => CigarOp::Deletion,
+            b'N' => CigarOp::Skip,
+            b'S' => CigarOp::SoftClip,
+            b'H' => CigarOp::HardClip,
+            b'P' => CigarOp::Padding,
+            b'=' => CigarOp::SequenceMatch,
+            b'X' => CigarOp::SequenceMismatch,
+            _ => return None,
+        })
+    }
+
+    /// The character of the operation.
+    fn as_char(&self) -> char {
+        match self {
+            CigarOp::Match => 'M',
+            CigarOp::Insertion => 'I',
+            CigarOp::Deletion => 'D',
+            CigarOp::Skip => 'N',
+            CigarOp::SoftClip => 'S',
+            CigarOp::HardClip => 'H',
+            CigarOp::Padding => 'P',
+            CigarOp::SequenceMatch => '=',
+            CigarOp::SequenceMismatch => 'X',
+        }
+    }
+
+    /// Whether the operation consumes bases of the `from` segment.
+    fn consumes_from(&self) -> bool {
+        matches!(
+            self,
+            CigarOp::Match
+                | CigarOp::Deletion
+                | CigarOp::Skip
+                | CigarOp::SequenceMatch
+                | CigarOp::SequenceMismatch
+        )
+    }
+
+    /// Whether the operation consumes bases of the `to` segment.
+    fn consumes_to(&self) -> bool {
+        matches!(
+            self,
+            CigarOp::Match
+                | CigarOp::Insertion
+                | CigarOp::SequenceMatch
+                | CigarOp::SequenceMismatch
+        )
+    }
+}
+
+/// The CIGAR string of a link, describing the overlap between the end
+/// of the `from` segment and the start of the `to` segment.
+///
+/// As in SAM, the `from` segment is the reference and the `to` segment the
+/// query. An unknown overlap (`*`) has no operations, so overlaps by nothing.
+/// Clipped bases and padding are not part of the overlap on either side.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Cigar(pub Vec<(usize, CigarOp)>);
+
+impl Cigar {
+    /// Parse a CIGAR string, e.g. `30M10D20M5I10M`, or `*`.
+    pub fn parse(cigar: &[u8]) -> Result<Self, GfatkError> {
+        let invalid = |message: String| GfatkError::InvalidCigar {
+            cigar: String::from_utf8_lossy(cigar).into(),
+            message,
+        };
+        if cigar == b"*" {
+            return Ok(Self::default());
+        }
+        if cigar.is_empty() {
+            return Err(invalid("it is empty.".into()));
+        }
+
+        let mut ops = Vec::new();
+        let mut length: Option<usize> = None;
+        for byte in cigar {
+            if byte.is_ascii_digit() {
+                let digit = (byte - b'0') as usize;
+                length = Some(
+                    length
+                        .unwrap_or(0)
+                        .checked_mul(10)
+                        .and_then(|l| l.checked_add(digit))
+                        .ok_or_else(|| invalid("an operation is too long.".into()))?,
+                );
+                continue;
+            }
+            let op = CigarOp::from_byte(*byte).ok_or_else(|| {
+                invalid(format!(
+                    "{} is not a CIGAR operation (one of MIDNSHP=X).",
+                    *byte as char
+                ))
+            })?;
+            let length = length
+                .take()
+                .ok_or_else(|| invalid(format!("operation {} has no length.", *byte as char)))?;
+            ops.push((length, op));
+        }
+        if length.is_some() {
+            return Err(invalid("it does not end with an operation.".into()));
+        }
+
+        Ok(Self(ops))
+    }
+
+    /// Whether the overlap is unknown (`*`).
+    pub fn is_unknown(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Whether the overlap is unknown, or a plain overlap (`<int>M`), which
+    /// is all that some GFA tools accept.
+    pub fn is_plain_overlap(&self) -> bool {
+        matches!(self.0[..], [] | [(_, CigarOp::Match)])
+    }
+
+    /// The number of bases at the end of the `from` segment in the overlap.
+    pub fn from_length(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|(_, op)| op.consumes_from())
+            .map(|(length, _)| length)
+            .sum()
+    }
+
+    /// The number of bases at the start of the `to` segment in the overlap,
+    /// which are dropped when the two segments are stitched together.
+    pub fn to_length(&self) -> usize {
+        self.0
+            .iter()
+            .filter(|(_, op)| op.consumes_to())
+            .map(|(length, _)| length)
+            .sum()
+    }
+
+    /// The overlap of the same link traversed on the other strand, where the
+    /// `from` and `to` segments swap roles.
+    ///
+    /// The operations are reversed, and insertions and deletions swapped.
+    /// A skip becomes an insertion, as there is no skip in the `to` segment.
+    pub fn flip(&self) -> Self {
+        Self(
+            self.0
+                .iter()
+                .rev()
+                .map(|(length, op)| {
+                    let op = match op {
+                        CigarOp::Insertion => CigarOp::Deletion,
+                        CigarOp::Deletion | CigarOp::Skip => CigarOp::Insertion,
+                        op => *op,
+                    };
+                    (*length, op)
+                })
+                .collect(),
+        )
+    }
+}
+
+impl fmt::Display for Cigar {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.is_unknown() {
+            return write!(f, "*");
+        }
+        for (length, op) in &self.0 {
+            write!(f, "{}{}", length, op.as_char())?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_cigar_parse() {
+        let plain = Cigar::parse(b"120M").unwrap();
+        assert_eq!((plain.from_length(), plain.to_length()), (120, 120));
+
+        let complex = Cigar::parse(b"30M10D20M5I10M").unwrap();
+        assert_eq!(complex.to_string(), "30M10D20M5I10M");
+        assert_eq!((complex.from_length(), complex.to_length()), (70, 65));
+
+        let flipped = complex.flip();
+        assert_eq!(flipped.to_string(), "10M5D20M10I30M");
+        assert_eq!((flipped.from_length(), flipped.to_length()), (65, 70));
+
+        let clipped = Cigar::parse(b"5S10=2X3H").unwrap();
+        assert_eq!((clipped.from_length(), clipped.to_length()), (12, 12));
+
+        let unknown = Cigar::parse(b"*").unwrap();
+        assert!(unknown.is_unknown());
+        assert_eq!((unknown.from_length(), unknown.to_length()), (0, 0));
+        assert_eq!(unknown.to_string(), "*");
+
+        assert!(plain.is_plain_overlap() && unknown.is_plain_overlap());
+        assert!(!complex.is_plain_overlap() && !clipped.is_plain_overlap());
+
+        for invalid in ["", "2Q", "M", "10M5", "10M*"] {
+            assert!(Cigar::parse(invalid.as_bytes()).is_err(), "{}", invalid);
+        }
+    }
+}
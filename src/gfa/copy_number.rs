@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, HashMap};
+
+/// Segments at least this long are used to fit the baseline coverage of a
+/// component, unless there are none.
+pub const LONG_SEGMENT_LENGTH: usize = 1000;
+
+/// The copy number of a segment, estimated from its coverage.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CopyNumber {
+    /// Index of the weakly connected component the segment is in.
+    pub component: usize,
+    /// The coverage of the segment.
+    pub coverage: f32,
+    /// The coverage of a single copy in the component.
+    pub baseline: f32,
+    /// The nearest whole number of copies, and at least one.
+    pub copy_number: usize,
+    /// How close the coverage is to the copy number, from 0 (half way to
+    /// the next copy number, or further) to 1 (exactly on it).
+    pub confidence: f32,
+}
+
+/// The copy numbers of the segments of a GFA, by segment ID.
+///
+/// Segments without coverage have no copy number.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct CopyNumbers(pub BTreeMap<usize, CopyNumber>);
+
+impl CopyNumbers {
+    /// Estimate copy numbers from the length and coverage of each segment.
+    ///
+    /// Each component (of segment ID's) is fitted separately, as e.g. the
+    /// mitochondrion and plastid of an assembly have very different coverage.
+    /// The baseline of a component is the median coverage of its segments of
+    /// at least `min_length`, as long segments are mostly single copy. The
+    /// median is not weighted by length, as a few long repeats can make up
+    /// most of the bases of a component. The mode is not offered, as coverage
+    /// is continuous, and with the few long segments of an organelle assembly
+    /// there is rarely a clear peak to take.
+    pub fn estimate(
+        segments: &HashMap<usize, (usize, f32)>,
+        components: &[Vec<usize>],
+        min_length: usize,
+    ) -> Self {
+        let mut copy_numbers = BTreeMap::new();
+
+        for (component, ids) in components.iter().enumerate() {
+            let covered: Vec<(usize, usize, f32)> = ids
+                .iter()
+                .filter_map(|id| segments.get(id).map(|(len, cov)| (*id, *len, *cov)))
+                .collect();
+
+            let long: Vec<f32> = covered
+                .iter()
+                .filter(|(_, len, _)| *len >= min_length)
+                .map(|(_, _, cov)| *cov)
+                .collect();
+            let baseline = match long.is_empty() {
+                true => median(covered.iter().map(|(_, _, cov)| *cov).collect()),
+                false => median(long),
+            };
+            let baseline = match baseline {
+                Some(b) if b > 0.0 => b,
+                _ => continue,
+            };
+
+            for (id, _, coverage) in covered {
+                let ratio = coverage / baseline;
+                let copy_number = (ratio.round() as usize).max(1);
+                let confidence = (1.0 - 2.0 * (ratio - copy_number as f32).abs()).clamp(0.0, 1.0);
+                copy_numbers.insert(
+                    id,
+                    CopyNumber {
+                        component,
+                        coverage,
+                        baseline,
+                        copy_number,
+                        confidence,
+                    },
+                );
+            }
+        }
+
+        Self(copy_numbers)
+    }
+
+    /// The copy number of a segment.
+    pub fn get(&self, id: usize) -> Option<&CopyNumber> {
+        self.0.get(&id)
+    }
+}
+
+/// The median coverage of the segments, or the lower of the two middle
+/// coverages if there is an even number.
+fn median(mut coverages: Vec<f32>) -> Option<f32> {
+    coverages.sort_by(|a, b| a.total_cmp(b));
+    coverages
+        .get(coverages.len().saturating_sub(1) / 2)
+        .copied()
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_copy_numbers() {
+        // id: (length, coverage)
+        let segments: HashMap<usize, (usize, f32)> = [
+            (0, (20000, 31.0)),
+            (1, (15000, 29.0)),
+            (2, (3000, 62.0)),
+            (3, (500, 75.0)),
+            (4, (100, 5.0)),
+            // a second component, with much higher coverage
+            (5, (9000, 400.0)),
+            (6, (8000, 410.0)),
+            (7, (500, 800.0)),
+        ]
+        .into_iter()
+        .collect();
+        let components = vec![vec![0, 1, 2, 3, 4], vec![5, 6, 7, 8]];
+
+        let copy_numbers = CopyNumbers::estimate(&segments, &components, 1000);
+
+        let cn = |id| copy_numbers.get(id).unwrap().copy_number;
+        assert_eq!((0..8).map(cn).collect::<Vec<_>>(), [1, 1, 2, 2, 1, 1, 1, 2]);
+
+        let first = copy_numbers.get(0).unwrap();
+        assert_eq!((first.component, first.baseline), (0, 31.0));
+        assert_eq!(first.confidence, 1.0);
+        // 75 / 31 is between 2 and 3 copies
+        assert!(copy_numbers.get(3).unwrap().confidence < 0.5);
+        // a segment with far less coverage than a single copy
+        assert_eq!(copy_numbers.get(4).unwrap().confidence, 0.0);
+        assert_eq!(copy_numbers.get(5).unwrap().baseline, 400.0);
+        // no coverage, so no copy number
+        assert!(copy_numbers.get(8).is_none());
+
+        // with no long segments, all of them are used
+        let copy_numbers = CopyNumbers::estimate(&segments, &components[..1], 100_000);
+        assert_eq!(copy_numbers.get(0).unwrap().baseline, 31.0);
+    }
+}
@@ -0,0 +1,181 @@
+use crate::error::GfatkError;
+use anyhow::{bail, Result};
+use gfa::optfields::{OptField, OptFieldVal};
+use std::fmt;
+
+/// A tag (optional field) which segment or link coverage can be read from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoverageTag {
+    /// `ec`, edge coverage (e.g. MBG, hifiasm).
+    Ec,
+    /// `ll`, segment coverage (e.g. MBG).
+    Ll,
+    /// `dp`, depth.
+    Dp,
+    /// `SC`, segment coverage (e.g. Shasta).
+    Sc,
+    /// `RC`, read count (GFA 1 spec).
+    Rc,
+    /// `KC`, k-mer count (GFA 1 spec).
+    Kc,
+}
+
+impl CoverageTag {
+    /// All the tags, in the default order of preference.
+    pub const ALL: [CoverageTag; 6] = [
+        CoverageTag::Ec,
+        CoverageTag::Ll,
+        CoverageTag::Dp,
+        CoverageTag::Sc,
+        CoverageTag::Rc,
+        CoverageTag::Kc,
+    ];
+
+    /// The two letters of the tag.
+    pub fn tag(&self) -> [u8; 2] {
+        match self {
+            CoverageTag::Ec => *b"ec",
+            CoverageTag::Ll => *b"ll",
+            CoverageTag::Dp => *b"dp",
+            CoverageTag::Sc => *b"SC",
+            CoverageTag::Rc => *b"RC",
+            CoverageTag::Kc => *b"KC",
+        }
+    }
+
+    /// Parse a tag from its two letters.
+    pub fn from_tag(tag: &str) -> Result<Self> {
+        match Self::ALL.iter().find(|t| t.tag() == tag.as_bytes()) {
+            Some(t) => Ok(*t),
+            None => bail!(GfatkError::Input(format!(
+                "{} is not a coverage tag (one of ec, ll, dp, SC, RC, KC).",
+                tag
+            ))),
+        }
+    }
+
+    /// Whether the tag is a count over the whole segment, so is divided by
+    /// the segment length to give a coverage.
+    pub fn is_count(&self) -> bool {
+        matches!(self, CoverageTag::Rc | CoverageTag::Kc)
+    }
+}
+
+impl fmt::Display for CoverageTag {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", String::from_utf8_lossy(&self.tag()))
+    }
+}
+
+/// Which tags segment and link coverage are read from, and what to do
+/// when a link has none of them.
+///
+/// The first of the tags which a segment or link has is used, wherever it
+/// is among the optional fields, and may be an integer or a float.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CoverageTags {
+    /// The tags, in order of preference.
+    pub tags: Vec<CoverageTag>,
+    /// Give links without coverage the lower coverage of the two
+    /// segments they join.
+    pub derive_edges: bool,
+}
+
+impl Default for CoverageTags {
+    fn default() -> Self {
+        Self {
+            tags: CoverageTag::ALL.to_vec(),
+            derive_edges: false,
+        }
+    }
+}
+
+impl CoverageTags {
+    /// The coverage tags given on the command line, with `--coverage-tags`
+    /// and `--derive-edge-coverage`.
+    pub fn from_matches(matches: &clap::ArgMatches) -> Result<Self> {
+        let tags = match matches.get_many::<String>("coverage-tags") {
+            Some(tags) => tags
+                .map(|tag| CoverageTag::from_tag(tag))
+                .collect::<Result<_>>()?,
+            None => CoverageTag::ALL.to_vec(),
+        };
+        Ok(Self {
+            tags,
+            derive_edges: matches.get_flag("derive-edge-coverage"),
+        })
+    }
+
+    /// The value of the first of the tags in these optional fields.
+    fn find(&self, options: &[OptField]) -> Option<(CoverageTag, f32)> {
+        self.tags.iter().find_map(|tag| {
+            let option = options.iter().find(|o| o.tag == tag.tag())?;
+            match option.value {
+                OptFieldVal::Int(i) => Some((*tag, i as f32)),
+                OptFieldVal::Float(f) => Some((*tag, f)),
+                _ => None,
+            }
+        })
+    }
+
+    /// The coverage of a segment of this length. Counts (`RC`, `KC`) are
+    /// divided by the length.
+    pub fn segment_coverage(&self, options: &[OptField], length: usize) -> Option<f32> {
+        let (tag, value) = self.find(options)?;
+        match tag.is_count() {
+            true if length > 0 => Some(value / length as f32),
+            _ => Some(value),
+        }
+    }
+
+    /// The coverage of a link, which is used as the weight of an edge.
+    pub fn link_coverage(&self, options: &[OptField]) -> Option<i64> {
+        self.find(options).map(|(_, value)| value.round() as i64)
+    }
+
+    /// The error for a segment or link without any of the tags.
+    pub fn missing(&self, message: String) -> GfatkError {
+        GfatkError::MissingTag {
+            tag: self.to_string(),
+            message,
+        }
+    }
+}
+
+impl fmt::Display for CoverageTags {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let tags: Vec<String> = self.tags.iter().map(|t| t.to_string()).collect();
+        write!(f, "{}", tags.join("/"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_coverage_tags() {
+        let options = vec![
+            OptField::new(b"LN", OptFieldVal::Int(100)),
+            OptField::new(b"KC", OptFieldVal::Int(2500)),
+            OptField::new(b"dp", OptFieldVal::Float(12.5)),
+        ];
+        let tags = CoverageTags::default();
+
+        // dp is preferred, even though it is not the first field
+        assert_eq!(tags.segment_coverage(&options, 100), Some(12.5));
+        assert_eq!(tags.link_coverage(&options), Some(13));
+
+        let tags = CoverageTags {
+            tags: vec![CoverageTag::from_tag("KC").unwrap()],
+            derive_edges: false,
+        };
+        assert_eq!(tags.segment_coverage(&options, 100), Some(25.0));
+        assert_eq!(tags.link_coverage(&options), Some(2500));
+        assert_eq!(tags.segment_coverage(&options[..1], 100), None);
+
+        assert!(CoverageTag::from_tag("LN").is_err());
+        assert_eq!(tags.missing("".into()).to_string(), "Missing KC tag: ");
+    }
+}
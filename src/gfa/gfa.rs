@@ -1,49 +1,975 @@
+use crate::error::GfatkError;
+use crate::gfa::optfields::OptionalTags;
 use crate::gfa::{
+    cigar::Cigar,
+    copy_number::{CopyNumbers, LONG_SEGMENT_LENGTH},
+    coverage::CoverageTags,
     gfa_string,
-    graph::{segments_subgraph, GFAdigraph},
+    graph::{flip_orientation, reverse_index, segments_subgraph, GFAdigraph, OrientedSegment},
+    names::SegmentNames,
+    walk::WalksJumps,
 };
-//use crate::path::GFAPath;
-//use crate::stats::GenomeType;
-//use crate::utils::{
-//    self, get_edge_coverage, parse_cigar, reverse_complement, GFAGraphLookups, GFAGraphPair,
-//};
+use crate::logging::Stage;
+use crate::path::{Circular, GFAPath, GFAPathElement};
+use crate::stats::GenomeType;
+use crate::utils::{self, reverse_complement};
 use anyhow::{bail, Context, Result};
-use gfa::gfa::{GFA};
-use gfa::optfields::{OptFieldVal, OptionalFields};
-use petgraph::graph::{Graph, NodeIndex, UnGraph};
+use gfa::gfa::{Orientation, Segment, GFA};
+use gfa::optfields::{OptField, OptFieldVal, OptFields};
+use log::{debug, warn};
+use petgraph::graph::NodeIndex;
 use std::collections::HashMap;
 
 /// A wrapper around GFA from the gfa crate
-/// TODO: make GFAtk generic for any segment name, not just usize.
-    /// Returns a tuple of GFAGraphLookups (a struct of indices/node names) and an directed GFA graph structure.
+///
+/// Segments are referred to by `usize` ID's, and their original names are kept
+/// in the [`SegmentNames`] table, which is used whenever segments are printed.
+/// Walk (`W`) and jump (`J`) lines, which the gfa crate does not parse, are
+/// kept alongside.
+#[derive(Clone)]
+pub struct GFAtk(
+    pub GFA<usize, OptionalTags>,
+    pub SegmentNames,
+    pub WalksJumps<usize>,
+);
+
+impl GFAtk {
+    /// Make a [`GFAtk`] from a GFA with any form of segment names, and its
+    /// walk and jump lines.
+    pub fn from_gfa(
+        gfa: GFA<Vec<u8>, OptionalTags>,
+        walks_jumps: WalksJumps<Vec<u8>>,
+    ) -> Result<Self> {
+        let (gfa, mut names) = SegmentNames::intern_gfa(&gfa);
+        let walks_jumps = names.intern_walks(&walks_jumps);
+        let gfatk = Self(gfa, names, walks_jumps);
+        gfatk.check_links()?;
+        Ok(gfatk)
+    }
+
+    /// Check that every link, walk and jump uses segments in the GFA.
+    fn check_links(&self) -> Result<()> {
+        let gfa = &self.0;
+        let names = &self.1;
+
+        let mut is_segment = vec![false; names.len()];
+        for segment in &gfa.segments {
+            is_segment[segment.name] = true;
+        }
+        for link in &gfa.links {
+            for id in [link.from_segment, link.to_segment] {
+                if !is_segment[id] {
+                    return Err(GfatkError::MissingSegment(names.name(id).to_string()))
+                        .with_context(|| {
+                            format!(
+                                "Link {}{} -> {}{} uses a segment which is not in the GFA",
+                                names.name(link.from_segment),
+                                link.from_orient,
+                                names.name(link.to_segment),
+                                link.to_orient,
+                            )
+                        });
+                }
+            }
+        }
+        for walk in &self.2.walks {
+            if let Some((id, _)) = walk.steps.iter().find(|(id, _)| !is_segment[*id]) {
+                return Err(GfatkError::MissingSegment(names.name(*id).to_string())).with_context(
+                    || {
+                        format!(
+                            "Walk {} uses a segment which is not in the GFA",
+                            walk.haplotype()
+                        )
+                    },
+                );
+            }
+        }
+        for jump in &self.2.jumps {
+            for id in [jump.from_segment, jump.to_segment] {
+                if !is_segment[id] {
+                    return Err(GfatkError::MissingSegment(names.name(id).to_string()))
+                        .with_context(|| {
+                            format!(
+                                "Jump {}{} -> {}{} uses a segment which is not in the GFA",
+                                names.name(jump.from_segment),
+                                jump.from_orient,
+                                names.name(jump.to_segment),
+                                jump.to_orient,
+                            )
+                        });
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Return the subgraph of the GFA containing only the segments with these ID's.
+    ///
+    /// Paths and walks are cut down to the pieces through these segments, so
+    /// the subgraph can be read back in.
+    pub fn subgraph(&self, segment_ids: Vec<usize>) -> Self {
+        let (lengths, overlaps) = match self.2.walks.is_empty() {
+            true => Default::default(),
+            false => (
+                self.0
+                    .segments
+                    .iter()
+                    .filter_map(|s| Some((s.name, known_segment_length(s)?)))
+                    .collect(),
+                self.link_overlaps(),
+            ),
+        };
+        let walks_jumps = self.2.subgraph(&segment_ids, |walk| {
+            Self::walk_step_spans(&walk.steps, &lengths, &overlaps)
+        });
+        Self(
+            segments_subgraph(&self.0, segment_ids),
+            self.1.clone(),
+            walks_jumps,
+        )
+    }
+
+    /// The start and end of each step of a walk on the sequence it spells,
+    /// relative to the start of the walk.
+    ///
+    /// As the `from` segment of a link is the reference, each step starts
+    /// where its overlap with the step before starts. Steps without a link
+    /// between them do not overlap, nor do those with an unknown overlap
+    /// (`*`). Returns `None` if the length of a segment is not known, or a
+    /// CIGAR string cannot be parsed.
+    fn walk_step_spans(
+        steps: &[(usize, Orientation)],
+        lengths: &HashMap<usize, usize>,
+        overlaps: &HashMap<(usize, Orientation, usize, Orientation), Option<usize>>,
+    ) -> Option<Vec<(usize, usize)>> {
+        let mut spans: Vec<(usize, usize)> = Vec::with_capacity(steps.len());
+        for (index, (segment_id, orient)) in steps.iter().enumerate() {
+            let length = *lengths.get(segment_id)?;
+            let start = match spans.last() {
+                Some((_, end)) => {
+                    let (from, from_orient) = steps[index - 1];
+                    let overlap = overlaps
+                        .get(&(from, from_orient, *segment_id, *orient))
+                        .copied()
+                        .unwrap_or(Some(0))?;
+                    end.saturating_sub(overlap)
+                }
+                None => 0,
+            };
+            spans.push((start, start + length));
+        }
+        Some(spans)
+    }
+
+    /// The number of bases of the `from` segment in the overlap of each link,
+    /// as traversed on either strand, or `None` if its CIGAR string cannot be
+    /// parsed.
+    fn link_overlaps(&self) -> HashMap<(usize, Orientation, usize, Orientation), Option<usize>> {
+        let mut overlaps = HashMap::new();
+        for link in &self.0.links {
+            let cigar = Cigar::parse(&link.overlap).ok();
+            overlaps.insert(
+                (
+                    link.from_segment,
+                    link.from_orient,
+                    link.to_segment,
+                    link.to_orient,
+                ),
+                cigar.as_ref().map(Cigar::from_length),
+            );
+            overlaps.insert(
+                (
+                    link.to_segment,
+                    flip_orientation(link.to_orient),
+                    link.from_segment,
+                    flip_orientation(link.from_orient),
+                ),
+                cigar.map(|c| c.flip().from_length()),
+            );
+        }
+        overlaps
+    }
+
+    /// Write the GFA to a string, with the original segment names.
+    pub fn to_gfa_string(&self) -> String {
+        gfa_string(&self.1.name_gfa(&self.0)) + &self.1.name_walks(&self.2).to_string()
+    }
+
+    /// A method to print a GFA to STDOUT, given a vector of sequence ID's to keep.
+    pub fn print_extract(&self, sequences_to_keep: Vec<usize>) {
+        print!("{}", self.subgraph(sequences_to_keep).to_gfa_string());
+    }
+
+    /// Returns the overlaps between all the segments in a GFA.
+    pub fn make_overlaps(&self, extend_length: usize) -> Result<Overlaps> {
+        let gfa = &self.0;
+        // tuple of (from: overlap - length (incl. overlap), to: overlap + length)
+        let mut from_to = Overlaps::new();
+        // outer loop over links
+        for link in &gfa.links {
+            // get all the info out of each link
+            let from_segment = link.from_segment;
+            let from_orient = link.from_orient;
+            let to_segment = link.to_segment;
+            let to_orient = link.to_orient;
+            let cigar = Cigar::parse(&link.overlap)?;
+
+            debug!(
+                "From segment {} ({}) to segment {} ({}), overlap: {}",
+                self.1.name(from_segment),
+                from_orient,
+                self.1.name(to_segment),
+                to_orient,
+                cigar
+            );
+
+            let mut from_seq: &[u8] = &[];
+            let mut to_seq: &[u8] = &[];
+
+            // get the from and to sequences.
+            for line in gfa.lines_iter() {
+                // if we meet a segment, let's do something
+                if let Some(s) = line.some_segment() {
+                    if s.name == from_segment && s.name == to_segment {
+                        from_seq = &s.sequence;
+                        to_seq = &s.sequence;
+                    } else if s.name == from_segment {
+                        from_seq = &s.sequence;
+                    } else if s.name == to_segment {
+                        to_seq = &s.sequence;
+                    }
+                }
+            }
+
+            // the bases of each segment in the overlap
+            let from_overlap = cigar.from_length();
+            let to_overlap = cigar.to_length();
+
+            // initiate so we can append to vec
+            let mut overlap_str_from_f: Option<String> = None;
+            let mut overlap_str_from_r: Option<String> = None;
+            let mut overlap_str_to_f: Option<String> = None;
+            let mut overlap_str_to_r: Option<String> = None;
+
+            // deal with the from's
+            match from_orient {
+                Orientation::Forward => {
+                    // do nothing
+                    // length - overlap - extend length at the end of the sequence.
+                    let overlap_seq = &from_seq
+                        .get(from_seq.len().saturating_sub(from_overlap + extend_length)..);
+                    // if the extend length is too long, it means that
+                    // we hit the start of the sequence, so take full slice.
+                    let overlap_str = match overlap_seq {
+                        Some(sl) => std::str::from_utf8(sl)
+                            .with_context(|| format!("Malformed UTF8: {:?}", sl))?,
+                        None => std::str::from_utf8(from_seq)
+                            .with_context(|| format!("Malformed UTF8: {:?}", from_seq))?,
+                    };
+                    overlap_str_from_f = Some(overlap_str.to_string());
+                }
+                // if the relative negative strand matches
+                // revcomp and take the end.
+                Orientation::Backward => {
+                    let revcomp = reverse_complement(from_seq);
+                    // let overlap_revcomp = revcomp[revcomp.len() - from_overlap - extend_length..].to_vec();
+                    let overlap_revcomp =
+                        revcomp.get(revcomp.len().saturating_sub(from_overlap + extend_length)..);
+
+                    let overlap_str = match overlap_revcomp {
+                        Some(sl) => String::from_utf8(sl.to_vec())
+                            .with_context(|| format!("Malformed UTF8: {:?}", sl))?,
+                        // take the whole thing.
+                        None => String::from_utf8(revcomp).context("Malformed UTF8")?,
+                    };
+
+                    overlap_str_from_r = Some(overlap_str);
+                }
+            }
+            // deal with the to's
+            // here we ignore the overlap, as that is
+            // captured above.
+            match to_orient {
+                Orientation::Forward => {
+                    // do nothing
+                    // let overlap = &to_seq[to_overlap..to_overlap + extend_length];
+                    let overlap_seq = &to_seq.get(to_overlap..to_overlap + extend_length);
+
+                    let overlap_str = match overlap_seq {
+                        Some(sl) => std::str::from_utf8(sl)
+                            .with_context(|| format!("Malformed UTF8: {:?}", sl))?,
+                        // from end of overlap to the end of the sequence
+                        None => std::str::from_utf8(&to_seq[to_overlap..]).with_context(|| {
+                            format!("Malformed UTF8: {:?}", &to_seq[to_overlap..])
+                        })?,
+                    };
+
+                    overlap_str_to_f = Some(overlap_str.to_string());
+                }
+                // if the relative negative strand matches
+                // revcomp and take the start.
+                Orientation::Backward => {
+                    let revcomp = reverse_complement(to_seq);
+                    // let overlap_revcomp = revcomp[to_overlap..to_overlap + extend_length].to_vec();
+                    let overlap_revcomp = revcomp.get(to_overlap..to_overlap + extend_length);
+
+                    let overlap_str = match overlap_revcomp {
+                        Some(sl) => String::from_utf8(sl.to_vec())
+                            .with_context(|| format!("Malformed UTF8: {:?}", sl))?,
+                        None => String::from_utf8(revcomp[to_overlap..].to_vec()).with_context(
+                            || format!("Malformed UTF8: {:?}", revcomp[to_overlap..].to_vec()),
+                        )?,
+                    };
+
+                    overlap_str_to_r = Some(overlap_str);
+                }
+            }
+
+            from_to.push(Overlap {
+                overlap_str_from_f,
+                overlap_str_from_r,
+                overlap_str_to_f,
+                overlap_str_to_r,
+                from_segment: self.1.name(from_segment).to_string(),
+                to_segment: self.1.name(to_segment).to_string(),
+                from_orient,
+                to_orient,
+            });
+        }
+        Ok(from_to)
+    }
+
+    /// The internal function called when `gfatk fasta` is called.
+    ///
+    /// Prints all segments of the GFA as-is.
+    pub fn print_sequences(&self, subgraph_index_header: Option<String>) -> Result<()> {
+        let gfa = &self.0;
+        let subgraph_index_header = subgraph_index_header.unwrap_or("".to_string());
+
+        for line in gfa.lines_iter() {
+            if let Some(s) = line.some_segment() {
+                let seq = std::str::from_utf8(&s.sequence)
+                    .with_context(|| format!("Malformed UTF8: {:?}", &s.sequence))?;
+                let id = self.1.name(s.name);
+                println!(">{}{}\n{}", id, subgraph_index_header, seq);
+            }
+        }
+        Ok(())
+    }
+
+    /// The coverage of a segment, from the first coverage tag it has (see [`CoverageTags`]).
+    fn segment_coverage(
+        segment: &Segment<usize, OptionalTags>,
+        coverage_tags: &CoverageTags,
+    ) -> Option<f32> {
+        coverage_tags.segment_coverage(&segment.optional, segment_length(segment))
+    }
+
+    /// The average coverage of the segments which have a coverage tag.
+    ///
+    /// Used in `gfatk stats`. Errors if no segment has a coverage tag.
+    fn get_coverage(&self, coverage_tags: &CoverageTags) -> Result<f32> {
+        let gfa = &self.0;
+
+        let covs: Vec<f32> = gfa
+            .segments
+            .iter()
+            .filter_map(|s| Self::segment_coverage(s, coverage_tags))
+            .collect();
+        if covs.is_empty() {
+            bail!(coverage_tags.missing("no segment has a coverage tag.".into()));
+        }
+        let len = covs.len() as f32;
+        let sum: f32 = covs.iter().sum();
+
+        Ok(sum / len)
+    }
+
+    /// Return the coverage and sequence length for a segment, given a segment name.
     ///
-    /// Most functionality of this binary is on directed graph structures
-    pub fn into_digraph(gfa: GFA<usize, OptionalFields>) -> Result<(HashMap::<usize,NodeIndex>, GFAdigraph)> {
-        //let gfa = &self.0;
-        eprintln!("[+]\tReading GFA into a directed graph.");
-        let mut gfa_graph: Graph<usize, ()> = Graph::with_capacity(gfa.segments.len(),gfa.links.len());
+    /// Note segment names are always `usize`.
+    pub fn node_seq_len_and_cov(
+        &self,
+        node: usize,
+        coverage_tags: &CoverageTags,
+    ) -> Result<(usize, f32)> {
+        let gfa = &self.0;
 
-        eprintln!("[+]\tPopulating {} nodes.",gfa.segments.len());
+        let mut seq_len = None;
+        let mut cov = None;
 
-        let mut graph_indices = HashMap::<usize,NodeIndex>::new();
-        // read the segments into graph nodes
-        // save the indexes for populating the edges
-        for node in &gfa.segments {
-            let index = gfa_graph.add_node(node.name);
-            graph_indices.insert(node.name,index);
+        for segment in &gfa.segments {
+            if segment.name == node {
+                seq_len = Some(segment.sequence.len());
+                cov = Self::segment_coverage(segment, coverage_tags);
+            }
         }
-        eprintln!("[+]\tPopulating {} edges.",gfa.links.len());
-        // populate the edges
-        for edge in &gfa.links {
-            let from = edge.from_segment;
-            let to = edge.to_segment;
 
-            let from_index = graph_indices[&from];
-            let to_index = graph_indices[&to];
+        Ok((
+            seq_len.context("No sequence length for each segment in GFA.")?,
+            cov.context("No segment coverage for each segment in GFA.")?,
+        ))
+    }
+
+    /// The internal function called in `gfatk stats`.
+    ///
+    /// Returns average GC%, average coverage, and total sequence length for a GFA (sub)graph.
+    pub fn sequence_stats(
+        &self,
+        genome_type: GenomeType,
+        tabular: bool,
+        coverage_tags: &CoverageTags,
+    ) -> Result<(f32, f32, usize)> {
+        let gfa = &self.0;
+
+        let cov = self.get_coverage(coverage_tags)?;
 
-            // add the edges
-            gfa_graph.add_edge(from_index, to_index, ());
+        let mut total_overlap_length = 0;
+        for link in &gfa.links {
+            total_overlap_length += Cigar::parse(&link.overlap)?.to_length();
         }
 
-        Ok((graph_indices, GFAdigraph(gfa_graph)))
+        let mut total_sequence_length = 0;
+        let mut gc_vec = Vec::new();
+
+        for segment in &gfa.segments {
+            let seq = &segment.sequence;
+            total_sequence_length += seq.len();
+
+            let gc = utils::gc_content(seq);
+            gc_vec.push(gc);
+        }
+
+        let avg_gc = gc_vec.iter().sum::<f32>() / gc_vec.len() as f32;
+
+        if !tabular && genome_type == GenomeType::None {
+            println!("\tTotal sequence length:\t{}", total_sequence_length);
+            println!("\tTotal sequence overlap length:\t{}", total_overlap_length);
+            println!(
+                "\tSequence length minus overlaps:\t{}",
+                total_sequence_length as i32 - total_overlap_length as i32
+            );
+            println!("\tGC content of total sequence:\t{}", avg_gc);
+            println!("\tAverage coverage of total segments:\t{}", cov);
+        }
+
+        Ok((avg_gc, cov, total_sequence_length))
+    }
+
+    /// Estimate the copy number of each segment with coverage, fitting a
+    /// baseline coverage to each of the `components` (see [`CopyNumbers::estimate`]).
+    pub fn copy_numbers(
+        &self,
+        components: &[Vec<usize>],
+        min_length: usize,
+        coverage_tags: &CoverageTags,
+    ) -> CopyNumbers {
+        let segments: HashMap<usize, (usize, f32)> = self
+            .0
+            .segments
+            .iter()
+            .filter_map(|seg| {
+                Self::segment_coverage(seg, coverage_tags)
+                    .map(|cov| (seg.name, (segment_length(seg), cov)))
+            })
+            .collect();
+
+        CopyNumbers::estimate(&segments, components, min_length)
+    }
+
+    /// The same GFA, with a `CN:i` tag on each segment with a copy number.
+    ///
+    /// Any `CN` tag already on a segment is replaced.
+    pub fn with_copy_numbers(&self, copy_numbers: &CopyNumbers) -> Self {
+        let mut gfa = self.clone();
+        for seg in &mut gfa.0.segments {
+            if let Some(cn) = copy_numbers.get(seg.name) {
+                seg.optional.retain(|o| &o.tag != b"CN");
+                seg.optional.push(OptField::new(
+                    b"CN",
+                    OptFieldVal::Int(cn.copy_number as i64),
+                ));
+            }
+        }
+        gfa
+    }
+
+    /// Returns a `HashMap` of the copy number of each node (segment) in the GFA (see [`GFAtk::copy_numbers`]).
+    ///
+    /// Both strands of a segment are given the same copy number. Every segment must have a coverage.
+    pub fn gen_cov_hash(
+        &self,
+        graph_indices: &HashMap<usize, NodeIndex>,
+        gfa_graph: &GFAdigraph,
+        coverage_tags: &CoverageTags,
+    ) -> Result<HashMap<NodeIndex, usize>> {
+        let gfa = &self.0;
+
+        if let Some(seg) = gfa
+            .segments
+            .iter()
+            .find(|s| Self::segment_coverage(s, coverage_tags).is_none())
+        {
+            bail!(coverage_tags.missing(format!(
+                "no coverage for segment {}.",
+                self.1.name(seg.name)
+            )));
+        }
+
+        let copy_numbers = self.copy_numbers(
+            &gfa_graph.weakly_connected_components(),
+            LONG_SEGMENT_LENGTH,
+            coverage_tags,
+        );
+
+        let mut rel_cov_map = HashMap::new();
+
+        for (id, cn) in &copy_numbers.0 {
+            let node_index = *graph_indices
+                .get(id)
+                .ok_or_else(|| GfatkError::MissingSegment(self.1.name(*id).to_string()))?;
+            rel_cov_map.insert(node_index, cn.copy_number);
+            rel_cov_map.insert(reverse_index(node_index), cn.copy_number);
+        }
+
+        Ok(rel_cov_map)
+    }
+
+    /// Take a [`GFAPath`] and print out the path
+    /// from a GFA.
+    ///
+    /// Currently implemented requires two loops of the GFA, and storage
+    /// of the sequences in a [`HashMap`].
+    ///
+    /// With `circular`, a path which closes on itself (either ending on its
+    /// first segment, or with a link from its last segment back to its first)
+    /// is printed as a circle, see [`Circular`].
+    pub fn from_path_cli(
+        &self,
+        mut path: GFAPath,
+        link_map: HashMap<String, Cigar>,
+        call: &str,
+        fasta_header: Option<&str>,
+        circular: Option<&Circular>,
+    ) -> Result<()> {
+        let gfa = &self.0;
+
+        // put all the segments in memory - easiest way for now.
+        let mut seg_map = HashMap::new();
+
+        for seg in &gfa.segments {
+            let id = seg.name;
+            let seq = seg.sequence.clone();
+
+            seg_map.insert(id, seq);
+        }
+
+        // the sequence of a segment on a strand, without the bases in the overlap
+        let oriented_sequence = |seg_id: usize, orientation: Orientation, overlap: &Cigar| {
+            let name = self.1.name(seg_id);
+            let seq = seg_map
+                .get(&seg_id)
+                .ok_or_else(|| GfatkError::MissingSegment(name.to_string()))?;
+            let seq = match orientation {
+                Orientation::Forward => seq.to_vec(),
+                Orientation::Backward => utils::reverse_complement(seq),
+            };
+            match seq.get(overlap.to_length()..) {
+                Some(trimmed) => Ok(trimmed.to_vec()),
+                None => Err(GfatkError::InvalidCigar {
+                    cigar: overlap.to_string(),
+                    message: format!("the overlap is longer than segment {}.", name),
+                }),
+            }
+        };
+
+        // the overlap of the link between two elements of the path
+        let overlap = |from: &GFAPathElement, to: &GFAPathElement| {
+            // format so we can match on the links map
+            let cigar_match = format!(
+                "{}{}|{}{}",
+                from.segment_id, from.orientation, to.segment_id, to.orientation
+            );
+
+            link_map.get(&cigar_match).ok_or_else(|| {
+                GfatkError::MissingLink(format!(
+                    "{} -> {}",
+                    self.1.oriented(OrientedSegment {
+                        seg_id: from.segment_id,
+                        orientation: from.orientation
+                    }),
+                    self.1.oriented(OrientedSegment {
+                        seg_id: to.segment_id,
+                        orientation: to.orientation
+                    })
+                ))
+            })
+        };
+
+        // the link from the end of a circular path back to its start
+        let closing = match circular {
+            Some(_) => self.closing_link(&mut path, &link_map),
+            None => None,
+        };
+
+        // the sequence of the path, and where each of its elements starts
+        // (before the overlap with the previous element is trimmed)
+        let mut sequence = Vec::new();
+        let mut starts = Vec::new();
+        for (index, el) in path.inner.iter().enumerate() {
+            // the first element is printed in full, unless the path is circular
+            let trim = match (index, &closing) {
+                (0, Some(cigar)) => cigar.clone(),
+                (0, None) => Cigar::default(),
+                _ => overlap(&path.inner[index - 1], el)?.clone(),
+            };
+            starts.push((sequence.len(), trim.to_length()));
+            sequence.extend(oriented_sequence(el.segment_id, el.orientation, &trim)?);
+        }
+
+        let mut reverse_complemented = false;
+        if let (Some(circular), Some(_)) = (circular, &closing) {
+            let starts: Vec<usize> = starts
+                .into_iter()
+                .map(|(start, trim)| (start + sequence.len() - trim) % sequence.len().max(1))
+                .collect();
+            reverse_complemented = circular.rotate(&mut sequence, &path, &starts, &self.1)?;
+        }
+        let strand = match reverse_complemented {
+            true => ":reverse_complement",
+            false => "",
+        };
+
+        match call {
+            // bit of a hack, sorry.
+            "path_all" | "linear" => println!(
+                ">{}{}",
+                fasta_header.context("A fasta header is needed for this path.")?,
+                strand
+            ),
+            "path" => println!(">{}{}", path.to_fasta_header(&self.1), strand),
+            _ => bail!("Should never reach here."),
+        }
+        println!("{}", std::str::from_utf8(&sequence)?);
+
+        Ok(())
+    }
+
+    /// The overlap of the link which closes a circular path, from its last
+    /// element back to its first.
+    ///
+    /// If the path ends where it started, the repeated element is removed
+    /// from the end. If the path does not close on itself, there is no such
+    /// link and it is printed as a linear path.
+    fn closing_link(&self, path: &mut GFAPath, link_map: &HashMap<String, Cigar>) -> Option<Cigar> {
+        let first = *path.inner.first()?;
+        let last = *path.inner.last()?;
+        if path.len() > 1
+            && (first.segment_id, first.orientation) == (last.segment_id, last.orientation)
+        {
+            path.inner.pop();
+        }
+        let last = *path.inner.last()?;
+
+        let closing = link_map
+            .get(&format!(
+                "{}{}|{}{}",
+                last.segment_id, last.orientation, first.segment_id, first.orientation
+            ))
+            .cloned();
+        if closing.is_none() {
+            warn!(
+                "The path does not close on itself ({} does not link to {}), so is printed linearly.",
+                self.1.oriented(OrientedSegment {
+                    seg_id: last.segment_id,
+                    orientation: last.orientation
+                }),
+                self.1.oriented(OrientedSegment {
+                    seg_id: first.segment_id,
+                    orientation: first.orientation
+                })
+            );
+        }
+        closing
+    }
+
+    /// Parses the P lines in a GFA
+    pub fn get_path_lines(&self) -> Result<Vec<(&str, String)>> {
+        let mut paths = Vec::new();
+
+        for path in &self.0.paths {
+            let id = std::str::from_utf8(&path.path_name)?;
+            let mut path_string = String::new();
+            for (seg_id, orientation) in path.iter() {
+                path_string += &format!("{}{orientation},", self.1.name(seg_id));
+            }
+            path_string.pop(); // remove the last ,
+            paths.push((id, path_string));
+        }
+        Ok(paths)
+    }
+}
+
+/// Returns a tuple of a map from segment ID to the node index of the forward strand of that segment, and a directed GFA graph structure.
+///
+/// Each segment is added as two nodes (one per strand), and each link is added
+/// along with its reverse complement. Edge coverage (`ec` tag) is stored on the
+/// edges if present.
+///
+/// Most functionality of this binary is on directed graph structures
+pub fn into_digraph(
+    gfa: &GFA<usize, OptionalTags>,
+    coverage_tags: &CoverageTags,
+) -> Result<(HashMap<usize, NodeIndex>, GFAdigraph)> {
+    let stage = Stage::start("Reading GFA into a directed graph");
+    let mut gfa_graph = GFAdigraph::with_capacity(gfa.segments.len(), gfa.links.len());
+
+    debug!("Populating {} nodes.", gfa.segments.len());
+
+    let mut graph_indices = HashMap::<usize, NodeIndex>::new();
+    // read the segments into graph nodes
+    // save the indexes for populating the edges
+    for node in &gfa.segments {
+        let index = gfa_graph.add_segment(node.name);
+        graph_indices.insert(node.name, index);
+    }
+    debug!("Populating {} edges.", gfa.links.len());
+    // populate the edges
+    for edge in &gfa.links {
+        let from = edge.from_segment;
+        let to = edge.to_segment;
+
+        let from_index = *graph_indices
+            .get(&from)
+            .ok_or_else(|| GfatkError::MissingSegment(from.to_string()))
+            .context("A link starts from a segment which is not in the GFA")?;
+        let to_index = *graph_indices
+            .get(&to)
+            .ok_or_else(|| GfatkError::MissingSegment(to.to_string()))
+            .context("A link ends on a segment which is not in the GFA")?;
+
+        // coverage is optional here, only some subcommands need it.
+        let ec = coverage_tags.link_coverage(&edge.optional);
+
+        // add the edges
+        gfa_graph.add_link(from_index, edge.from_orient, to_index, edge.to_orient, ec);
+    }
+    if coverage_tags.derive_edges {
+        let segment_coverage: HashMap<usize, f32> = gfa
+            .segments
+            .iter()
+            .filter_map(|s| Some((s.name, GFAtk::segment_coverage(s, coverage_tags)?)))
+            .collect();
+        gfa_graph.derive_edge_coverage(&segment_coverage);
+    }
+    stage.finish();
+
+    Ok((graph_indices, gfa_graph))
+}
+
+/// The length of a segment, from its `LN` tag if it has no sequence (`*`).
+fn segment_length(segment: &Segment<usize, OptionalTags>) -> usize {
+    known_segment_length(segment).unwrap_or(0)
+}
+
+/// The length of a segment, or `None` if it has no sequence (`*`) and no `LN` tag.
+fn known_segment_length(segment: &Segment<usize, OptionalTags>) -> Option<usize> {
+    match &segment.sequence[..] {
+        b"*" => match segment.optional.get_field(b"LN").map(|ln| &ln.value) {
+            Some(OptFieldVal::Int(ln)) => Some(*ln as usize),
+            _ => None,
+        },
+        sequence => Some(sequence.len()),
+    }
+}
+
+/// Overlap from one segment to another.
+pub struct Overlap {
+    /// From segment forward.
+    pub overlap_str_from_f: Option<String>,
+    /// From segment reverse.
+    pub overlap_str_from_r: Option<String>,
+    /// To segment forward.
+    pub overlap_str_to_f: Option<String>,
+    /// To segment reverse.
+    pub overlap_str_to_r: Option<String>,
+    /// Name of from segment.
+    pub from_segment: String,
+    /// Name of to segment.
+    pub to_segment: String,
+    /// Orientation of from segment.
+    pub from_orient: Orientation,
+    /// Orientation of to segment.
+    pub to_orient: Orientation,
+}
+
+/// A vector of `Overlap` structs.
+pub struct Overlaps(Vec<Overlap>);
+
+impl Overlaps {
+    /// Create a new instance of `Overlaps`.
+    fn new() -> Self {
+        Self(Vec::new())
+    }
+    /// Append to `Overlaps`, adding another `Overlap`.
+    fn push(&mut self, add: Overlap) {
+        self.0.push(add)
+    }
+    /// Print overlaps to STDOUT.
+    pub fn print(self, extend_length: usize) {
+        // long winded...
+        for o in self.0 {
+            // unwrap None -> zero length string.
+            let ff = o.overlap_str_from_f.unwrap_or("".to_string());
+            let fr = o.overlap_str_from_r.unwrap_or("".to_string());
+            let tf = o.overlap_str_to_f.unwrap_or("".to_string());
+            let tr = o.overlap_str_to_r.unwrap_or("".to_string());
+            let from_seg = o.from_segment;
+            let to_seg = o.to_segment;
+            let from_orient = o.from_orient;
+            let to_orient = o.to_orient;
+
+            println!(
+                ">{}({})->{}({}): extend = {}\n{}{}{}{}",
+                from_seg, from_orient, to_seg, to_orient, extend_length, ff, fr, tf, tr
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::gfa::coverage::CoverageTag;
+    use crate::load::load_gfatk;
+    use crate::stats::GenomeType;
+
+    // the GFA -> GFAtk structure used in tests below.
+    fn make_gfa(path: &str) -> GFAtk {
+        load_gfatk(path).unwrap()
+    }
+
+    #[test]
+    fn test_gfa_sequence_stats() {
+        let gfa = make_gfa("./tests/test_linear.gfa");
+
+        // could be mitochondria/chloroplast
+        let (gc, cov, len) = gfa
+            .sequence_stats(GenomeType::Mitochondria, false, &CoverageTags::default())
+            .unwrap();
+
+        assert!(cov == 40.0);
+        assert!(gc == 0.39523807);
+        assert!(len == 18);
+    }
+
+    #[test]
+    fn test_gen_cov_hash() {
+        let gfa = make_gfa("./tests/test_linear.gfa");
+
+        let (graph_indices, gfa_graph) = into_digraph(&gfa.0, &CoverageTags::default()).unwrap();
+
+        let cov_hash = gfa
+            .gen_cov_hash(&graph_indices, &gfa_graph, &CoverageTags::default())
+            .unwrap();
+
+        assert_eq!(
+            cov_hash
+                .get(&graph_indices[&gfa.1.id(b"11").unwrap()])
+                .unwrap(),
+            &1
+        );
+        assert_eq!(
+            cov_hash
+                .get(&graph_indices[&gfa.1.id(b"12").unwrap()])
+                .unwrap(),
+            &2
+        );
+        assert_eq!(
+            cov_hash
+                .get(&graph_indices[&gfa.1.id(b"13").unwrap()])
+                .unwrap(),
+            &1
+        );
+        // the reverse strand has the same coverage
+        assert_eq!(
+            cov_hash
+                .get(&reverse_index(graph_indices[&gfa.1.id(b"12").unwrap()]))
+                .unwrap(),
+            &2
+        );
+    }
+
+    // every link is present in the graph on both strands
+    #[test]
+    fn test_into_digraph() {
+        let gfa = make_gfa("./tests/test_circular.gfa");
+
+        let (graph_indices, gfa_graph) = into_digraph(&gfa.0, &CoverageTags::default()).unwrap();
+
+        assert_eq!(gfa_graph.node_count(), 2);
+        // 1+ -> 2+, 2+ -> 1+, and the reverse complements 2- -> 1-, 1- -> 2-
+        assert_eq!(gfa_graph.edge_count(), 4);
+        assert!(gfa_graph
+            .0
+            .find_edge(
+                reverse_index(graph_indices[&gfa.1.id(b"2").unwrap()]),
+                reverse_index(graph_indices[&gfa.1.id(b"1").unwrap()])
+            )
+            .is_some());
+    }
+
+    // the coverage tags are passed in, so two sets can be used side by side
+    #[test]
+    fn test_into_digraph_coverage_tags() {
+        let gfa = make_gfa("./tests/test_coverage_tags.gfa");
+        let rc_kc = CoverageTags {
+            tags: vec![CoverageTag::Rc, CoverageTag::Kc],
+            derive_edges: false,
+        };
+
+        let (_, ec_graph) = into_digraph(&gfa.0, &CoverageTags::default()).unwrap();
+        let (_, rc_graph) = into_digraph(&gfa.0, &rc_kc).unwrap();
+
+        assert!(ec_graph.0.edge_weights().all(|w| *w == Some(1)));
+        assert!(rc_graph.0.edge_weights().all(|w| *w == Some(3)));
+        // KC is divided by the segment length, and no segment has ec
+        assert_eq!(gfa.get_coverage(&rc_kc).unwrap(), 40.0);
+        let ec = CoverageTags {
+            tags: vec![CoverageTag::Ec],
+            derive_edges: false,
+        };
+        assert!(gfa.get_coverage(&ec).is_err());
+    }
+
+    #[test]
+    fn test_walk_step_spans() {
+        use Orientation::*;
+        let gfa = make_gfa("./tests/test_names.gfa");
+        let lengths = HashMap::from([(0, 5), (1, 6), (2, 7)]);
+        let overlaps = gfa.link_overlaps();
+
+        // ACCTT + (T)CAAGG reverse complemented + (CTTGA)TT
+        let spans = GFAtk::walk_step_spans(
+            &[(0, Forward), (1, Backward), (2, Forward)],
+            &lengths,
+            &overlaps,
+        );
+        assert_eq!(spans, Some(vec![(0, 5), (1, 7), (2, 9)]));
+        // the same walk on the other strand
+        let spans = GFAtk::walk_step_spans(
+            &[(2, Backward), (1, Forward), (0, Backward)],
+            &lengths,
+            &overlaps,
+        );
+        assert_eq!(spans, Some(vec![(0, 7), (2, 8), (4, 9)]));
+        // no link between the steps, and a segment of unknown length
+        let spans = GFAtk::walk_step_spans(&[(2, Forward), (0, Forward)], &lengths, &overlaps);
+        assert_eq!(spans, Some(vec![(0, 7), (7, 12)]));
+        assert_eq!(
+            GFAtk::walk_step_spans(&[(3, Forward)], &lengths, &overlaps),
+            None
+        );
     }
+}
@@ -1,222 +1,1473 @@
-//use crate::gfa::gfa::GFAtk;
-//use crate::utils::{format_usize_to_kb, GFAGraphLookups};
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::GFAtk;
+use crate::gfa::names::SegmentNames;
+use crate::gfa::walk::kept_runs;
+use crate::path::GFAPath;
+use crate::utils::format_usize_to_kb;
 use anyhow::{bail, Context, Result};
+use bstr::ByteSlice;
 use gfa::gfa::Orientation;
-use gfa::gfa::GFA;
+use gfa::gfa::{Path, GFA};
 use gfa::optfields::OptFields;
 use itertools::Itertools;
+use log::{info, warn};
 use petgraph::{
-    graph::{Graph, IndexType, NodeIndex},
-    visit::{EdgeRef, IntoNodeIdentifiers, IntoNodeReferences, NodeIndexable, NodeRef},
-    Directed,
-    Direction::Outgoing,
-    Undirected,
+    graph::{Graph, NodeIndex},
+    visit::EdgeRef,
+    Direction::{Incoming, Outgoing},
 };
+use rayon::prelude::*;
+use serde::{Serialize, Serializer};
+use std::cmp::Reverse;
 use std::collections::HashMap;
-use std::collections::HashSet;
+use std::collections::{BTreeSet, BinaryHeap, HashSet};
+use std::fmt;
 
-/// A wrapper of petgraph's undirected `Graph` struct, applied to a GFA. No weights.
+/// A segment read in a particular orientation. These are the nodes of a [`GFAdigraph`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct OrientedSegment {
+    /// The segment ID.
+    pub seg_id: usize,
+    /// The strand the segment is read on.
+    pub orientation: Orientation,
+}
 
+impl OrientedSegment {
+    /// The same segment, read on the opposite strand.
+    pub fn flip(&self) -> Self {
+        Self {
+            seg_id: self.seg_id,
+            orientation: flip_orientation(self.orientation),
+        }
+    }
+}
 
-// weights are the orientations, used at various points, and an optional
-// coverage weight, used in gfatk linear.
-// GFA's should always specify Links in a specific direction..?
-// so digraphs should be where all the functionality lies.
+impl fmt::Display for OrientedSegment {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", self.seg_id, self.orientation)
+    }
+}
 
-/// A wrapper of petgraph's directed `Graph` struct, applied to a GFA. The edge weights included are the `Orientation`'s of the adjacent segments, and the coverage of this edge.
-pub struct GFAdigraph(pub Graph<usize, ()>);
+// serialised in the same form as a path step, e.g. "11+"
+impl Serialize for OrientedSegment {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+/// Switch an `Orientation` to the opposite strand.
+pub fn flip_orientation(orientation: Orientation) -> Orientation {
+    match orientation {
+        Orientation::Forward => Orientation::Backward,
+        Orientation::Backward => Orientation::Forward,
+    }
+}
+
+/// Given the node index of the forward strand of a segment, return the
+/// node index of that segment in `orientation`.
+pub fn oriented_index(forward: NodeIndex, orientation: Orientation) -> NodeIndex {
+    match orientation {
+        Orientation::Forward => forward,
+        Orientation::Backward => reverse_index(forward),
+    }
+}
+
+/// The node index of the forward strand of the segment of a node.
+pub fn forward_index(node: NodeIndex) -> NodeIndex {
+    NodeIndex::new(node.index() & !1)
+}
+
+/// The node index of the same segment on the opposite strand.
+///
+/// Segments are always added as a (forward, backward) pair of nodes, so the
+/// two strands of a segment only differ in the lowest bit of their index.
+pub fn reverse_index(node: NodeIndex) -> NodeIndex {
+    NodeIndex::new(node.index() ^ 1)
+}
+
+// A GFA is a bidirected graph: a link `A+ -> B-` can equally be read as
+// `B+ -> A-`. We model this with two nodes per segment (one per strand), and
+// every link adds its reverse complement too, so walks, reachability and
+// strongly connected components in the underlying petgraph `Graph` always
+// respect the strand of each segment.
+
+/// A superbubble: a subgraph entered only through its source, and left only
+/// through its sink, with no cycles inside.
+///
+/// A simple bubble is a superbubble whose paths have at most one segment.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Superbubble {
+    /// The oriented segment every path through the superbubble starts from.
+    pub source: NodeIndex,
+    /// The oriented segment every path through the superbubble ends at.
+    pub sink: NodeIndex,
+    /// The oriented segments inside the superbubble, in topological order.
+    pub inside: Vec<NodeIndex>,
+}
+
+/// A wrapper of petgraph's directed `Graph` struct, applied to a GFA. Each segment is represented by two [`OrientedSegment`] nodes, one per strand. The edge weights are the coverage of the link, if present.
+pub struct GFAdigraph(pub Graph<OrientedSegment, Option<i64>>);
 
 impl GFAdigraph {
+    /// Create an empty graph, with capacity for a number of segments and links.
+    pub fn with_capacity(segments: usize, links: usize) -> Self {
+        Self(Graph::with_capacity(segments * 2, links * 2))
+    }
+
+    /// Add both strands of a segment to the graph, returning the node index
+    /// of the forward strand.
+    pub fn add_segment(&mut self, seg_id: usize) -> NodeIndex {
+        let gfa_graph = &mut self.0;
+
+        let forward = gfa_graph.add_node(OrientedSegment {
+            seg_id,
+            orientation: Orientation::Forward,
+        });
+        gfa_graph.add_node(OrientedSegment {
+            seg_id,
+            orientation: Orientation::Backward,
+        });
+
+        forward
+    }
+
+    /// Add a link, and its reverse complement, to the graph.
+    ///
+    /// `from` and `to` are the node indices of the forward strands of the
+    /// segments (as returned by [`GFAdigraph::add_segment`]). Links which are
+    /// already present (e.g. because the GFA lists both a link and its
+    /// reverse complement) are not duplicated.
+    pub fn add_link(
+        &mut self,
+        from: NodeIndex,
+        from_orient: Orientation,
+        to: NodeIndex,
+        to_orient: Orientation,
+        coverage: Option<i64>,
+    ) {
+        let gfa_graph = &mut self.0;
+
+        let source = oriented_index(from, from_orient);
+        let target = oriented_index(to, to_orient);
+
+        for (a, b) in [
+            (source, target),
+            (reverse_index(target), reverse_index(source)),
+        ] {
+            if gfa_graph.find_edge(a, b).is_none() {
+                gfa_graph.add_edge(a, b, coverage);
+            }
+        }
+    }
+
+    /// Return the segment ID and orientation of a node.
+    pub fn oriented_segment(&self, node: NodeIndex) -> OrientedSegment {
+        self.0[node]
+    }
+
+    /// Return the sorted, deduplicated segment ID's of a collection of nodes.
+    ///
+    /// Useful as a strand-independent name for a set of nodes, such as a strongly connected component.
+    pub fn segment_ids(&self, nodes: &[NodeIndex]) -> Vec<usize> {
+        let mut seg_ids: Vec<usize> = nodes.iter().map(|n| self.0[*n].seg_id).collect();
+        seg_ids.sort_unstable();
+        seg_ids.dedup();
+        seg_ids
+    }
+
+    /// Return the oriented segments just outside a set of nodes, such as a strongly connected component.
+    ///
+    /// The first vector holds the entries (sources of edges into `nodes`), the
+    /// second the exits (targets of edges out of `nodes`). Both are sorted and
+    /// deduplicated. A node on the opposite strand of a member is outside the set.
+    pub fn boundary(&self, nodes: &[NodeIndex]) -> (Vec<OrientedSegment>, Vec<OrientedSegment>) {
+        let gfa_graph = &self.0;
+        let members: HashSet<NodeIndex> = nodes.iter().copied().collect();
+
+        let mut entries = BTreeSet::new();
+        let mut exits = BTreeSet::new();
+
+        for node in nodes {
+            for edge in gfa_graph.edges_directed(*node, Incoming) {
+                if !members.contains(&edge.source()) {
+                    entries.insert(gfa_graph[edge.source()]);
+                }
+            }
+            for edge in gfa_graph.edges_directed(*node, Outgoing) {
+                if !members.contains(&edge.target()) {
+                    exits.insert(gfa_graph[edge.target()]);
+                }
+            }
+        }
+
+        (entries.into_iter().collect(), exits.into_iter().collect())
+    }
+
+    /// Check that every link in the graph has edge coverage, read from one of
+    /// the `coverage_tags` (e.g. `ec`).
+    pub fn ensure_edge_coverage(
+        &self,
+        names: &SegmentNames,
+        coverage_tags: &CoverageTags,
+    ) -> Result<()> {
+        let gfa_graph = &self.0;
+
+        if let Some(edge) = gfa_graph.edge_references().find(|e| e.weight().is_none()) {
+            bail!(coverage_tags.missing(format!(
+                "no edge coverage for the link {} -> {}.",
+                names.oriented(gfa_graph[edge.source()]),
+                names.oriented(gfa_graph[edge.target()])
+            )));
+        }
+        Ok(())
+    }
+
+    /// Give each edge without coverage the lower coverage of the two segments
+    /// it joins, if they both have one.
+    pub fn derive_edge_coverage(&mut self, segment_coverage: &HashMap<usize, f32>) {
+        let gfa_graph = &mut self.0;
+
+        for edge in gfa_graph.edge_indices() {
+            let (source, target) = match gfa_graph.edge_endpoints(edge) {
+                Some(endpoints) if gfa_graph[edge].is_none() => endpoints,
+                _ => continue,
+            };
+            let from = segment_coverage.get(&gfa_graph[source].seg_id);
+            let to = segment_coverage.get(&gfa_graph[target].seg_id);
+            if let (Some(from), Some(to)) = (from, to) {
+                gfa_graph[edge] = Some(from.min(*to).round() as i64);
+            }
+        }
+    }
+
     /// The main function called from `gfatk dot`.
     ///
     /// It is a somewhat modified, simplified version of this:
     /// <https://docs.rs/petgraph/latest/src/petgraph/dot.rs.html#1-349>
     ///
-    /// Generating a DOT language output of a GFA file.
+    /// Generating a DOT language output of a GFA file. Each segment is drawn
+    /// once, and each link is drawn once (its reverse complement is skipped).
+    pub fn dot(&self, gfa: GFAtk, coverage_tags: &CoverageTags) -> Result<()> {
+        let gfa_graph = &self.0;
+        static INDENT: &str = "    ";
+
+        println!("digraph GFA {{");
+        // print nodes, once per segment
+        for node in gfa_graph.node_indices().step_by(2) {
+            let e = node.index() / 2;
+            let w = gfa_graph[node].seg_id;
+            let meta = gfa.node_seq_len_and_cov(w, coverage_tags)?;
+            println!(
+                // see https://stackoverflow.com/questions/20516143/graphviz-dot-different-fontsizes-in-same-label
+                "{}{} [ label = <<FONT POINT-SIZE=\'20\'>{}</FONT><br/><FONT POINT-SIZE=\'10\'>L: {}</FONT><br/><FONT POINT-SIZE=\'10\'>C: {}</FONT>> ];",
+                INDENT, e, gfa.1.name(w), format_usize_to_kb(meta.0), meta.1
+            );
+        }
+        // print edges
+        for edge in gfa_graph.edge_references() {
+            let source = edge.source();
+            let target = edge.target();
+            // this is the reverse complement of a link we print elsewhere
+            if (reverse_index(target), reverse_index(source)) < (source, target) {
+                continue;
+            }
+
+            let from = source.index() / 2;
+            let to = target.index() / 2;
+            let from_o = gfa_graph[source].orientation;
+            let to_o = gfa_graph[target].orientation;
+
+            let arrowhead_shape = match to_o {
+                Orientation::Forward => "ornormal",
+                Orientation::Backward => "olnormal",
+            };
+
+            let ec = edge
+                .weight()
+                .context(format!("No edge weight for edge {:?}", edge))?;
+
+            println!("{}{} -> {} [ label = \"  {}  \" taillabel = \"  {}  \" headlabel = \"  {}  \" arrowhead = \"{}\" ];",
+                INDENT,
+                from,
+                to,
+                ec,
+                from_o,
+                to_o,
+                arrowhead_shape
+            );
+        }
+
+        println!("}}");
+
+        Ok(())
+    }
+
+    /// Return the strongly connected components of the graph, once per strand pair.
+    ///
+    /// Every component has a mirror image on the opposite strand. Only the copy
+    /// which reads its lowest segment forward is kept (a component containing
+    /// both strands of a segment is its own mirror image). Components are sorted
+    /// by their segment ID's.
+    pub fn strongly_connected_components(&self) -> Vec<Vec<NodeIndex>> {
+        let sccs = self
+            .weakly_connected_nodes()
+            .iter()
+            .flat_map(|component| self.component_sccs(component))
+            .collect();
+        self.canonical_sccs(sccs)
+    }
+
+    /// As [`GFAdigraph::strongly_connected_components`], but each weakly connected
+    /// component is searched in parallel, on a pool of `threads` threads (0 uses
+    /// all available cores).
+    ///
+    /// A strongly connected component never crosses two weakly connected ones,
+    /// so they can be searched independently.
+    pub fn par_strongly_connected_components(&self, threads: usize) -> Result<Vec<Vec<NodeIndex>>> {
+        let pool = rayon::ThreadPoolBuilder::new()
+            .num_threads(threads)
+            .build()
+            .context("Could not build the thread pool")?;
+
+        let components = self.weakly_connected_nodes();
+        let sccs = pool.install(|| {
+            components
+                .par_iter()
+                .flat_map_iter(|component| self.component_sccs(component))
+                .collect()
+        });
+        Ok(self.canonical_sccs(sccs))
+    }
+
+    /// Keep one of each mirror image pair of strongly connected components, sorted.
+    fn canonical_sccs(&self, sccs: Vec<Vec<NodeIndex>>) -> Vec<Vec<NodeIndex>> {
+        let gfa_graph = &self.0;
+
+        let mut sccs: Vec<Vec<NodeIndex>> = sccs
+            .into_iter()
+            .filter(|scc| {
+                let first = scc.iter().map(|n| gfa_graph[*n].seg_id).min();
+                scc.iter().any(|n| {
+                    Some(gfa_graph[*n].seg_id) == first
+                        && gfa_graph[*n].orientation == Orientation::Forward
+                })
+            })
+            .collect();
+        sccs.sort_by_cached_key(|scc| self.segment_ids(scc));
+
+        sccs
+    }
+
+    /// Tarjan's algorithm over the nodes of one weakly connected component.
+    ///
+    /// The depth first search keeps its own stack rather than recursing, so it
+    /// cannot overflow the call stack on long chains of segments. `nodes` must
+    /// be closed under edges (as a weakly connected component is).
+    fn component_sccs(&self, nodes: &[NodeIndex]) -> Vec<Vec<NodeIndex>> {
+        let graph = &self.0;
+        // position of each node in `nodes`, which the state below is indexed by
+        let local: HashMap<NodeIndex, usize> =
+            nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+        let mut index: Vec<Option<usize>> = vec![None; nodes.len()];
+        let mut lowlink: Vec<usize> = vec![0; nodes.len()];
+        let mut on_stack: Vec<bool> = vec![false; nodes.len()];
+        let mut stack: Vec<usize> = Vec::new();
+        let mut next_index = 0;
+        let mut sccs = Vec::new();
+
+        for root in 0..nodes.len() {
+            if index[root].is_some() {
+                continue;
+            }
+            // each frame is a node, and the successors it has left to visit
+            let mut call_stack = Vec::new();
+            index[root] = Some(next_index);
+            lowlink[root] = next_index;
+            next_index += 1;
+            stack.push(root);
+            on_stack[root] = true;
+            call_stack.push((root, graph.neighbors(nodes[root])));
+
+            while let Some((v, successors)) = call_stack.last_mut() {
+                let v = *v;
+                match successors.next() {
+                    Some(w) => {
+                        let w = local[&w];
+                        match index[w] {
+                            None => {
+                                index[w] = Some(next_index);
+                                lowlink[w] = next_index;
+                                next_index += 1;
+                                stack.push(w);
+                                on_stack[w] = true;
+                                call_stack.push((w, graph.neighbors(nodes[w])));
+                            }
+                            Some(w_index) if on_stack[w] => {
+                                lowlink[v] = lowlink[v].min(w_index);
+                            }
+                            _ => (),
+                        }
+                    }
+                    None => {
+                        call_stack.pop();
+                        if let Some((parent, _)) = call_stack.last() {
+                            lowlink[*parent] = lowlink[*parent].min(lowlink[v]);
+                        }
+                        if Some(lowlink[v]) == index[v] {
+                            let mut scc = Vec::new();
+                            while let Some(w) = stack.pop() {
+                                on_stack[w] = false;
+                                scc.push(nodes[w]);
+                                if w == v {
+                                    break;
+                                }
+                            }
+                            sccs.push(scc);
+                        }
+                    }
+                }
+            }
+        }
+
+        sccs
+    }
+
     // we want weakly connected components, as there may only be an edge in one
     // orientation (perhaps unlikely... but still)
 
     /// Split the GFA digraph into subgraphs which are the weakly connected components of the graph.
     ///
-    /// Taken from <https://github.com/Qiskit/retworkx/blob/79900cf8da0c0665ac5ce1ccb0f57373434b14b8/src/connectivity/mod.rs>
+    /// Both strands of a segment always belong to the same component. Returns the segment ID's of each component.
+    ///
+    /// Modified from <https://github.com/Qiskit/retworkx/blob/79900cf8da0c0665ac5ce1ccb0f57373434b14b8/src/connectivity/mod.rs>
+    pub fn weakly_connected_components(&self) -> Vec<Vec<usize>> {
+        let graph = &self.0;
+        self.weakly_connected_nodes()
+            .iter()
+            .map(|component| {
+                component
+                    .iter()
+                    .filter(|n| graph[**n].orientation == Orientation::Forward)
+                    .map(|n| graph[*n].seg_id)
+                    .collect()
+            })
+            .collect()
+    }
+
+    /// The nodes (both strands) of each weakly connected component, in node order.
+    fn weakly_connected_nodes(&self) -> Vec<Vec<NodeIndex>> {
+        let graph = &self.0;
+        let mut seen: HashSet<NodeIndex> = HashSet::with_capacity(graph.node_count());
+        let mut out_vec: Vec<Vec<NodeIndex>> = Vec::new();
+
+        for node in graph.node_indices() {
+            if seen.contains(&node) {
+                continue;
+            }
+            // ordered so that segments come out in the order of the GFA.
+            let mut component_set: BTreeSet<NodeIndex> = BTreeSet::new();
+            let mut stack = vec![node];
+
+            while let Some(bfs_node) = stack.pop() {
+                if !seen.insert(bfs_node) {
+                    continue;
+                }
+                component_set.insert(bfs_node);
+                // the other strand of this segment
+                stack.push(reverse_index(bfs_node));
+                stack.extend(graph.neighbors_undirected(bfs_node));
+            }
+
+            out_vec.push(component_set.into_iter().collect());
+        }
+        out_vec
+    }
+
+    /// Find the superbubbles of the graph, with at most `max_size` oriented
+    /// segments inside each one.
+    ///
+    /// Each superbubble is found on both strands; only the strand with the
+    /// lower (source, sink) node indices is returned. Superbubbles are
+    /// returned in order of their source, and nested superbubbles are
+    /// returned as well as those they are nested in.
+    ///
+    /// Modified from Onodera et al. 2013 (<https://doi.org/10.1007/978-3-642-40453-5_26>),
+    /// as implemented in BubbleGun (<https://github.com/fawaz-dabbaghieh/bubble_gun>).
+    pub fn superbubbles(&self, max_size: usize) -> Vec<Superbubble> {
+        let graph = &self.0;
+
+        graph
+            .node_indices()
+            .filter(|n| graph.neighbors_directed(*n, Outgoing).unique().count() > 1)
+            .filter_map(|n| self.superbubble_from(n, max_size))
+            .filter(|b| (b.source, b.sink) <= (reverse_index(b.sink), reverse_index(b.source)))
+            .collect()
+    }
+
+    /// The superbubble starting at `source`, if there is one.
+    fn superbubble_from(&self, source: NodeIndex, max_size: usize) -> Option<Superbubble> {
+        let graph = &self.0;
+        // nodes are visited once all of their parents are, so in topological order
+        let mut visited: Vec<NodeIndex> = Vec::new();
+        let mut visited_set: HashSet<NodeIndex> = HashSet::new();
+        // nodes with a visited parent, which have not been visited yet
+        let mut seen: HashSet<NodeIndex> = HashSet::from([source]);
+        let mut stack = vec![source];
+
+        while let Some(node) = stack.pop() {
+            visited.push(node);
+            visited_set.insert(node);
+            seen.remove(&node);
+            if visited.len() > max_size + 1 {
+                return None;
+            }
+
+            let children: Vec<NodeIndex> =
+                graph.neighbors_directed(node, Outgoing).unique().collect();
+            // a tip
+            if children.is_empty() {
+                return None;
+            }
+            for child in children {
+                // a cycle
+                if child == source || visited_set.contains(&child) {
+                    return None;
+                }
+                seen.insert(child);
+                if graph
+                    .neighbors_directed(child, Incoming)
+                    .all(|p| visited_set.contains(&p))
+                {
+                    stack.push(child);
+                }
+            }
+
+            if stack.len() == 1 && seen.len() == 1 {
+                let sink = stack[0];
+                if graph.contains_edge(sink, source) {
+                    return None;
+                }
+                return Some(Superbubble {
+                    source,
+                    sink,
+                    inside: visited[1..].to_vec(),
+                });
+            }
+        }
+        None
+    }
+
+    /// The paths through a superbubble from its source to its sink (excluding
+    /// both), up to `max_paths` of them.
+    pub fn superbubble_paths(&self, bubble: &Superbubble, max_paths: usize) -> Vec<Vec<NodeIndex>> {
+        let graph = &self.0;
+        let inside: HashSet<NodeIndex> = bubble.inside.iter().copied().collect();
+        let mut paths = Vec::new();
+        let mut stack = vec![(bubble.source, Vec::new())];
+
+        while let Some((node, path)) = stack.pop() {
+            if paths.len() >= max_paths {
+                break;
+            }
+            for child in graph.neighbors_directed(node, Outgoing).unique() {
+                if child == bubble.sink {
+                    paths.push(path.clone());
+                } else if inside.contains(&child) {
+                    let mut path = path.clone();
+                    path.push(child);
+                    stack.push((child, path));
+                }
+            }
+        }
+        paths.truncate(max_paths);
+        paths
+    }
+
+    /// The number of paths through a superbubble from its source to its sink.
+    pub fn superbubble_path_count(&self, bubble: &Superbubble) -> u64 {
+        let graph = &self.0;
+        let mut counts: HashMap<NodeIndex, u64> = HashMap::from([(bubble.source, 1)]);
+
+        for node in bubble.inside.iter().chain([&bubble.sink]) {
+            let count = graph
+                .neighbors_directed(*node, Incoming)
+                .unique()
+                .map(|p| counts.get(&p).copied().unwrap_or(0))
+                .fold(0u64, |a, b| a.saturating_add(b));
+            counts.insert(*node, count);
+        }
+        counts[&bubble.sink]
+    }
+
+    /// Whether a superbubble is a simple bubble, where every path through it
+    /// has at most one segment.
+    pub fn is_simple_bubble(&self, bubble: &Superbubble) -> bool {
+        let graph = &self.0;
+        bubble.inside.iter().all(|n| {
+            graph
+                .neighbors_directed(*n, Incoming)
+                .all(|p| p == bubble.source)
+                && graph
+                    .neighbors_directed(*n, Outgoing)
+                    .all(|c| c == bubble.sink)
+        })
+    }
+
+    /// The times each segment may be visited in a path, indexed by segment
+    /// (half the index of its forward node).
+    ///
+    /// This is its relative coverage in `rel_coverage_map`, or once if this is not supplied.
+    fn segment_budget(&self, rel_coverage_map: Option<&HashMap<NodeIndex, usize>>) -> Vec<usize> {
+        self.0
+            .node_indices()
+            .step_by(2)
+            .map(|n| {
+                rel_coverage_map
+                    .and_then(|m| m.get(&n).copied())
+                    .unwrap_or(1)
+            })
+            .collect()
+    }
+
+    /// The outgoing edges of each node, with their coverage (or zero).
+    fn weighted_children(&self) -> Vec<Vec<(NodeIndex, i64)>> {
+        let graph = &self.0;
+        graph
+            .node_indices()
+            .map(|node| {
+                graph
+                    .edges_directed(node, Outgoing)
+                    .map(|e| (e.target(), e.weight().unwrap_or(0)))
+                    .collect()
+            })
+            .collect()
+    }
 
     /// The main function called from `gfatk linear`.
     ///
-    /// This function will generate the longest path through the GFA, by
-    /// filtering the output of `all_paths`, and choosing the path with
-    /// the highest cumulative edge coverage.
+    /// This function finds the path through the GFA with the highest
+    /// cumulative edge coverage, and on a tie, the longest such path.
+    ///
+    /// A segment may appear in a path (on either strand) as many times as its
+    /// relative coverage in `rel_coverage_map`, or once if this is not supplied.
+    ///
+    /// Rather than enumerate every path between every pair of nodes, this is
+    /// a depth first branch and bound search. Each time a path is extended,
+    /// the most it could go on to gain is bounded by the visits it has left
+    /// to the segments it can still reach (see [`ReachableBound`]). This is
+    /// worked out once for each node added to the path, and gives a bound for
+    /// each of its children too, so children which cannot beat the best path
+    /// found so far are never visited, and the others are tried most
+    /// promising first. A path is also abandoned if it can no longer reach a
+    /// segment which could have come before its start, as adding that
+    /// segment to the start would be a better path.
+    ///
+    /// A path and its reverse complement have the same score, so each is only
+    /// taken from the search starting on the lower of their two first nodes.
+    /// If the search runs for more than `max_steps` steps (usually
+    /// [`MAX_SEARCH_STEPS`]), the best path found so far is returned, and the fasta header also gives the
+    /// most any path could score (`coverage_bound`). This is the highest of
+    /// the bounds on the paths not yet searched: those through the children
+    /// still to try on the current path, and those from the starts after it,
+    /// so the gap to the chosen path's coverage is proven.
+    ///
+    /// Links without edge coverage count as none, so check the graph with
+    /// [`GFAdigraph::ensure_edge_coverage`] first.
+    ///
+    /// Returns the chosen path, the segment ID's not in the path, and a fasta header.
+    pub fn highest_coverage_path(
+        &self,
+        rel_coverage_map: Option<&HashMap<NodeIndex, usize>>,
+        names: &SegmentNames,
+        max_steps: usize,
+    ) -> Result<(GFAPath, Vec<usize>, String)> {
+        let graph = &self.0;
+
+        // segments are indexed by their forward node
+        let segment = |node: NodeIndex| node.index() / 2;
+        let segment_count = graph.node_count() / 2;
+
+        let budget = self.segment_budget(rel_coverage_map);
+        let children = self.weighted_children();
+        let mut bound = ReachableBound::new(&children, &budget);
+        let mut children_lost = Vec::new();
+
+        // the visits to each segment of a path on just the start, and the
+        // segments which could come before the start
+        let start_path = |start: NodeIndex| {
+            let mut visits = vec![0usize; segment_count];
+            visits[segment(start)] += 1;
+            let before_start: Vec<usize> = children[reverse_index(start).index()]
+                .iter()
+                .map(|(node, _)| segment(*node))
+                .collect();
+            (visits, before_start)
+        };
+
+        // the best path so far, with its (score, length)
+        let mut final_path: Option<(Vec<NodeIndex>, (i64, usize))> = None;
+        let mut steps = 0;
+        // if the search is stopped, the most a path not yet searched could
+        // score, and the start it was stopped on
+        let mut stopped: Option<(Option<i64>, NodeIndex)> = None;
+
+        'search: for start in graph.node_indices() {
+            if budget[segment(start)] == 0 {
+                continue;
+            }
+            let (mut visits, before_start) = start_path(start);
+
+            let mut score = 0;
+            // each node on the path, the coverage of the link into it,
+            // and the children still to try from it.
+            let mut path: Vec<(NodeIndex, i64, Vec<Child>)> = Vec::new();
+            // the next node, the coverage of the link into it, and the
+            // bound it was tried with (none for the start)
+            let mut next = Some((start, 0, None));
+
+            loop {
+                if let Some((node, coverage, node_bound)) = next.take() {
+                    steps += 1;
+                    if steps > max_steps {
+                        // the paths through this node or the children still
+                        // to try, the start itself is bounded below
+                        let unsearched = path
+                            .iter()
+                            .flat_map(|(_, _, to_try)| to_try.iter().map(|(_, _, b)| b.0))
+                            .chain(node_bound.map(|(s, _)| s))
+                            .max();
+                        let start = match node_bound {
+                            Some(_) => NodeIndex::new(start.index() + 1),
+                            None => start,
+                        };
+                        stopped = Some((unsearched, start));
+                        break 'search;
+                    }
+
+                    let length = path.len() + 1;
+                    let best = final_path.as_ref().map(|(_, best)| *best);
+                    // the reverse complement is taken from the search starting on its first node
+                    if start <= reverse_index(node)
+                        && best.is_none_or(|best| (score, length) > best)
+                    {
+                        let nodes = path.iter().map(|(n, _, _)| *n).chain([node]).collect();
+                        final_path = Some((nodes, (score, length)));
+                    }
+
+                    let best = final_path.as_ref().map(|(_, best)| *best);
+                    let mut to_try = Vec::new();
+                    if let Some((reach_score, reach_length)) =
+                        bound.bound(node, &visits, start, &before_start, &mut children_lost)
+                    {
+                        for (child, child_coverage, lost) in &children_lost {
+                            let child_bound = (score + reach_score - lost, length + reach_length);
+                            if best.is_none_or(|best| child_bound > best) {
+                                to_try.push((*child, *child_coverage, child_bound));
+                            }
+                        }
+                    }
+                    // popped off the end, so the most promising child is tried
+                    // first, and on a tie the first link
+                    to_try.reverse();
+                    to_try.sort_by_key(|(_, _, child_bound)| *child_bound);
+                    path.push((node, coverage, to_try));
+                }
+
+                let Some((node, coverage, to_try)) = path.last_mut() else {
+                    break;
+                };
+                match to_try.pop() {
+                    Some((child, child_coverage, child_bound)) => {
+                        let s = segment(child);
+                        // the best so far may have improved since the children were ordered
+                        let best = final_path.as_ref().map(|(_, best)| *best);
+                        if best.is_some_and(|best| child_bound <= best) {
+                            to_try.clear();
+                        } else if visits[s] < budget[s] {
+                            visits[s] += 1;
+                            score += child_coverage;
+                            next = Some((child, child_coverage, Some(child_bound)));
+                        }
+                    }
+                    None => {
+                        // all the children are done, so step back
+                        visits[segment(*node)] -= 1;
+                        score -= *coverage;
+                        path.pop();
+                    }
+                }
+            }
+        }
+
+        // the paths from the starts not yet searched, bounded as a path on
+        // just the start would be
+        let coverage_bound = stopped.map(|(unsearched, stopped_on)| {
+            let from_starts = graph
+                .node_indices()
+                .skip(stopped_on.index())
+                .filter(|start| budget[segment(*start)] > 0)
+                .filter_map(|start| {
+                    let (visits, before_start) = start_path(start);
+                    bound
+                        .bound(start, &visits, start, &before_start, &mut children_lost)
+                        .map(|(score, _)| score)
+                });
+            let best = final_path.as_ref().map(|(_, (score, _))| *score);
+            from_starts
+                .chain(unsearched)
+                .chain(best)
+                .max()
+                .unwrap_or_default()
+        });
+
+        // explicit error out here
+        let (final_path, (coverage, _)) = match final_path {
+            Some(p) => p,
+            None => bail!("There was no highest coverage path."),
+        };
+        if let Some(coverage_bound) = coverage_bound {
+            warn!(
+                "Stopped searching for paths after {} steps. The chosen path has a coverage of {}, and no path has more than {}.",
+                max_steps, coverage, coverage_bound
+            );
+        }
+
+        // the links used, counting a link and its reverse complement once
+        let canonical_link = |from: NodeIndex, to: NodeIndex| {
+            (from, to).min((reverse_index(to), reverse_index(from)))
+        };
+        let all_links: HashSet<_> = graph
+            .edge_references()
+            .map(|e| canonical_link(e.source(), e.target()))
+            .collect();
+        let used_links: HashSet<_> = final_path
+            .windows(2)
+            .map(|pair| canonical_link(pair[0], pair[1]))
+            .collect();
+
+        let final_path =
+            GFAPath::from_segments(&final_path.iter().map(|n| graph[*n]).collect_vec());
+        let chosen_path_string = final_path.to_fasta_header(names);
+
+        info!("Chosen path through graph: {}", chosen_path_string);
+
+        // make a vector of segments not in the final path
+        // these will be passed later and printed to a fasta.
+        let final_path_set: HashSet<_> = final_path.segments().map(|e| e.seg_id).collect();
+
+        let difference_ids: Vec<usize> = graph
+            .node_indices()
+            .step_by(2)
+            .map(|e| graph[e].seg_id)
+            .filter(|item| !final_path_set.contains(item))
+            .collect();
+
+        let unused_segments = format!("{}/{}", difference_ids.len(), segment_count);
+        let unused_links = format!("{}/{}", all_links.len() - used_links.len(), all_links.len());
+        info!(
+            "Highest cumulative coverage path = {}. Unused: {} segments, {} links.",
+            coverage, unused_segments, unused_links
+        );
+
+        // the fasta header should contain the tool, path information, coverage,
+        // and how much of the graph is left out of the path
+        let mut fasta_header = format!(
+            "gfatk_linear:path={}:coverage={}:unused_segments={}:unused_links={}",
+            chosen_path_string, coverage, unused_segments, unused_links
+        );
+        if let Some(coverage_bound) = coverage_bound {
+            fasta_header += &format!(":coverage_bound={}", coverage_bound);
+        }
+
+        Ok((final_path, difference_ids, fasta_header))
+    }
+
+    /// The circular walks through the graph, used in `gfatk linear --circular`.
+    ///
+    /// A circular walk returns to the oriented segment it started from, and
+    /// may pass through each segment (on either strand) as many times as its
+    /// relative coverage in `rel_coverage_map`, or once if this is not supplied.
+    /// So a multipartite mitochondrion gives both its master circle and the
+    /// sub-circles made by recombination across its repeats.
+    ///
+    /// Each circle is returned once, however it is rotated and on whichever
+    /// strand, starting from whichever of its segments is first in the GFA.
+    /// Circles are sorted by their cumulative edge coverage (including the
+    /// link which closes the circle), then their length, and only the best
+    /// `max_walks` of them are kept, wherever they turn up in the search. If
+    /// the search runs for more than [`MAX_SEARCH_STEPS`] steps, the best
+    /// circles found so far are returned. As for
+    /// [`GFAdigraph::highest_coverage_path`], links without edge coverage
+    /// count as none.
+    pub fn circular_walks(
+        &self,
+        rel_coverage_map: Option<&HashMap<NodeIndex, usize>>,
+        max_walks: usize,
+    ) -> Result<Vec<(GFAPath, i64)>> {
+        let graph = &self.0;
+
+        // segments are indexed by their forward node
+        let segment = |node: NodeIndex| node.index() / 2;
+        let budget = self.segment_budget(rel_coverage_map);
+        let children = self.weighted_children();
+
+        // the same circle, rotated or on the other strand, has the same canonical form
+        let canonical = |walk: &[NodeIndex]| {
+            let reverse: Vec<NodeIndex> = walk.iter().rev().map(|n| reverse_index(*n)).collect();
+            (0..walk.len())
+                .flat_map(|i| {
+                    [
+                        walk[i..].iter().chain(&walk[..i]).copied().collect_vec(),
+                        reverse[i..]
+                            .iter()
+                            .chain(&reverse[..i])
+                            .copied()
+                            .collect_vec(),
+                    ]
+                })
+                .min()
+                .unwrap_or_default()
+        };
+
+        let mut seen: HashSet<Vec<NodeIndex>> = HashSet::new();
+        // the best walks so far, with the worst on top. On a tie, the walk
+        // found first is kept.
+        let mut walks = BinaryHeap::new();
+        let mut steps = 0;
+
+        'search: for start in graph.node_indices() {
+            if budget[segment(start)] == 0 {
+                continue;
+            }
+            let mut visits = vec![0usize; budget.len()];
+            visits[segment(start)] += 1;
+
+            // each node on the walk, the coverage of the link into it,
+            // and the index of its next child to try.
+            let mut walk: Vec<(NodeIndex, i64, usize)> = vec![(start, 0, 0)];
+            let mut score = 0;
+
+            while let Some((node, coverage, next_child)) = walk.last_mut() {
+                let (child, child_coverage) = match children[node.index()].get(*next_child) {
+                    Some(c) => *c,
+                    None => {
+                        // all the children are done, so step back
+                        visits[segment(*node)] -= 1;
+                        score -= *coverage;
+                        walk.pop();
+                        continue;
+                    }
+                };
+                *next_child += 1;
+
+                steps += 1;
+                if steps > MAX_SEARCH_STEPS {
+                    warn!(
+                        "Stopped searching for circular walks after {} steps. Some may be missing.",
+                        MAX_SEARCH_STEPS
+                    );
+                    break 'search;
+                }
+
+                if child == start {
+                    let nodes: Vec<NodeIndex> = walk.iter().map(|(n, _, _)| *n).collect();
+                    if seen.insert(canonical(&nodes)) {
+                        let key = (score + child_coverage, nodes.len(), Reverse(seen.len()));
+                        walks.push(Reverse((key, nodes)));
+                        if walks.len() > max_walks {
+                            walks.pop();
+                        }
+                    }
+                    continue;
+                }
 
-    /// Simple wrapper of `Graph.node_count()` in petgraph.
+                // each circle is found from the segment in it with the lowest ID
+                let s = segment(child);
+                if s < segment(start) || visits[s] >= budget[s] {
+                    continue;
+                }
+                visits[s] += 1;
+                score += child_coverage;
+                walk.push((child, child_coverage, 0));
+            }
+        }
+
+        if seen.len() > max_walks {
+            warn!(
+                "Found {} circular walks, so only the {} with the highest coverage are kept.",
+                seen.len(),
+                max_walks
+            );
+        }
+
+        // highest coverage first, then the longest
+        Ok(walks
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(((coverage, _, _), nodes))| {
+                let segments = nodes.iter().map(|n| graph[*n]).collect_vec();
+                (GFAPath::from_segments(&segments), coverage)
+            })
+            .collect())
+    }
+
+    /// The number of segments in the graph.
+    ///
+    /// This is half the number of nodes in the underlying petgraph `Graph`.
     pub fn node_count(&self) -> usize {
         let gfa_graph = &self.0;
 
-        gfa_graph.node_count()
+        gfa_graph.node_count() / 2
     }
 
-    /// Simple wrapper of `Graph.edge_count()` in petgraph.
+    /// The number of oriented edges in the graph (each link counts once per strand).
     pub fn edge_count(&self) -> usize {
         let gfa_graph = &self.0;
 
         gfa_graph.edge_count()
     }
 
+    /// Trim a graph to include only segments connected to two or more other segments.
+    ///
+    /// This algorithm will loop for as long as the longest branch in the GFA yields a segment connected to only a single segment.
+    pub fn trim(&self, names: &SegmentNames) -> Vec<usize> {
+        let gfa_graph = &self.0;
+
+        // the neighbouring segments of each segment, on either strand
+        let mut neighbours: Vec<(NodeIndex, HashSet<NodeIndex>)> = Vec::new();
+        for node_index in gfa_graph.node_indices().step_by(2) {
+            let segment_neighbours = gfa_graph
+                .neighbors_undirected(node_index)
+                .chain(gfa_graph.neighbors_undirected(reverse_index(node_index)))
+                .map(forward_index)
+                .collect::<HashSet<_>>();
+            neighbours.push((node_index, segment_neighbours));
+        }
+
+        // initiate new hashset for the nodes we remove
+        let mut removed_nodes = HashSet::new();
+        loop {
+            let mut removed_this_iteration = false;
+            // iterate over the segments
+            for (node_index, segment_neighbours) in &neighbours {
+                if removed_nodes.contains(node_index) {
+                    continue;
+                }
+                // if there are fewer than two neighbours which have not been removed
+                if segment_neighbours.difference(&removed_nodes).count() < 2 {
+                    removed_nodes.insert(*node_index);
+                    removed_this_iteration = true;
+                }
+            }
+            // there are no more nodes to delete.
+            if !removed_this_iteration {
+                break;
+            }
+        }
+
+        let mut kept = Vec::new();
+        for (node_index, _) in &neighbours {
+            let seg_id = gfa_graph[*node_index].seg_id;
+            if removed_nodes.contains(node_index) {
+                // print for user info
+                info!("Removed segment {} from GFA.", names.name(seg_id));
+            } else {
+                kept.push(seg_id);
+            }
+        }
+
+        kept
+    }
+
+    /// The algorithm called in `gfatk extract`.
+    ///
+    /// The number of iterations of searching for neighbouring segments can be modified.
+    /// `collect_sequence_names` are the node indices of the segments to start from.
+    ///
+    /// It's a naive algorithm, but it's fast enough for our purposes.
+    pub fn recursive_search(
+        &self,
+        names: &SegmentNames,
+        iterations: i32,
+        mut collect_sequence_names: Vec<NodeIndex>,
+    ) -> Vec<usize> {
+        let gfa_graph = &self.0;
+
+        info!(
+            "Recursively searching around segments {} at depth {}",
+            names.join(&self.segment_ids(&collect_sequence_names)),
+            iterations
+        );
+
+        for _ in 0..iterations {
+            // collect all the neighbours of all the current node indices
+            // on both strands.
+            for index in collect_sequence_names.clone() {
+                for strand in [index, reverse_index(index)] {
+                    for c in gfa_graph.neighbors_undirected(strand) {
+                        // could possibly add a conditional in here.
+                        collect_sequence_names.push(forward_index(c));
+                    }
+                }
+            }
+            // add sorting and deduping here too
+            // yes otherwise vectors are enormous.
+            collect_sequence_names.sort();
+            collect_sequence_names.dedup();
+        }
+
+        // turn node indexes into sequence ID's
+        collect_sequence_names
+            .iter()
+            .map(|index| gfa_graph[forward_index(*index)].seg_id)
+            .collect()
+    }
 }
 
-/// A function generic over certain types of `Directed` petgraph `Graph`s.
+/// A child of a node on a path, the coverage of the link to it, and the
+/// (score, length) a path through it could reach at best.
+type Child = (NodeIndex, i64, (i64, usize));
+
+/// A link, from and to node indices.
+type Link = (usize, usize);
+
+/// Bounds what a path could go on to add to its score and length, from the
+/// segments it can still reach. The buffers are kept between calls, so each
+/// bound only costs a traversal of the part of the graph still reachable.
 ///
-/// Given a graph, a start node, an end node, and optionally a map of the coverage of each node, compute all simple paths between these nodes.
+/// Each node has a potential, and the gain of a step is its coverage plus
+/// the potential of the node it leaves, less the potential of the node it
+/// enters. Along a path these cancel out, bar the first and last nodes, so a
+/// path's score is the sum of its gains, less the potential of its first
+/// node, plus that of its last. Each segment is entered at most as many times
+/// as it may be visited, so a path scores at most the best (positive) gain
+/// into each segment it could enter, times its visits left, plus the highest
+/// potential it could end on, less that of its start.
 ///
-/// Modified from: <https://github.com/Ninjani/rosalind/blob/e22ecf2c9f0935d970b137684029957c0850d63f/t_ba11b/src/lib.rs>
-pub fn all_paths<T, U, Ix: IndexType>(
-    graph: &Graph<T, U, Directed, Ix>,
-    start_node: NodeIndex<Ix>,
-    end_node: NodeIndex<Ix>,
-    rel_coverage_map: Option<&HashMap<NodeIndex<Ix>, usize>>,
-    index: usize,
-) -> Result<Vec<Vec<NodeIndex<Ix>>>> {
-    match rel_coverage_map {
-        Some(cov_map) => {
-            // for the set of visited nodes
-            let mut visited = HashMap::new();
-            visited.insert(start_node, 1);
-            // a counter for recursion depth.
-            let depth = 0;
-            match recursive_path_finder_incl_coverage(
-                graph,
-                start_node,
-                end_node,
-                &mut visited,
-                cov_map,
-                depth,
-                index,
-            ) {
-                Some(p) => p,
-                None => {
-                    // copy of the chunk below!
-                    // so if we go past the self imposed stack limit
-                    // we default to our other (not including coverage) method.
-                    let mut visited = HashSet::new();
-                    visited.insert(start_node);
-                    recursive_path_finder_no_coverage(graph, start_node, end_node, &mut visited)
+/// This holds whatever the potentials are, so they only decide how tight the
+/// bound is, and never whether the search finds the best path. With every
+/// potential zero, it is the best link into each segment, which counts a
+/// node as the way into more segments than a path could leave it for. The
+/// potentials are fitted once (see [`ReachableBound::fit_potentials`]) to
+/// lower the bound over the whole graph.
+struct ReachableBound<'a> {
+    /// The outgoing edges of each node, with their coverage.
+    children: &'a [Vec<(NodeIndex, i64)>],
+    /// The times each segment may be visited.
+    budget: &'a [usize],
+    /// The potential of each node.
+    potential: Vec<i64>,
+    /// The nodes reached so far in this call.
+    seen: Vec<bool>,
+    /// The best gain of a step into each segment reached so far in this
+    /// call, from a node the path could still be on.
+    best_in: Vec<Option<i64>>,
+    /// What to reset at the end of a call.
+    reached: Vec<NodeIndex>,
+    stack: Vec<NodeIndex>,
+}
+
+impl<'a> ReachableBound<'a> {
+    /// The number of rounds of fitting the potentials. On tangled graphs of
+    /// 100 to 200 segments, four times as many lower the bound over the whole
+    /// graph by less than 0.5%.
+    const ROUNDS: usize = 500;
+
+    fn new(children: &'a [Vec<(NodeIndex, i64)>], budget: &'a [usize]) -> Self {
+        let mut bound = Self {
+            children,
+            budget,
+            potential: vec![0; children.len()],
+            seen: vec![false; children.len()],
+            best_in: vec![None; budget.len()],
+            reached: Vec::new(),
+            stack: Vec::new(),
+        };
+        // rounding the potentials gives a (slightly) different bound, but
+        // still a bound, as it holds whatever they are.
+        bound.potential = bound
+            .fit_potentials()
+            .iter()
+            .map(|p| p.round() as i64)
+            .collect();
+        bound
+    }
+
+    /// The bound on paths through the whole graph with these potentials, and
+    /// for each segment, the link with the best gain into it (if it gains
+    /// anything). Also the nodes with the highest and lowest potential, where
+    /// the paths could end and start.
+    fn graph_bound(&self, potential: &[f64]) -> (f64, Vec<Option<Link>>, usize, usize) {
+        let mut best_in: Vec<Option<(f64, usize, usize)>> = vec![None; self.budget.len()];
+        for (from, edges) in self.children.iter().enumerate() {
+            for (child, coverage) in edges {
+                let to = child.index();
+                let gain = *coverage as f64 + potential[from] - potential[to];
+                let best = &mut best_in[to / 2];
+                if gain > 0.0 && best.is_none_or(|(g, _, _)| gain > g) {
+                    *best = Some((gain, from, to));
                 }
             }
         }
-        None => {
-            let mut visited = HashSet::new();
-            visited.insert(start_node);
-            recursive_path_finder_no_coverage(graph, start_node, end_node, &mut visited)
+        let by_potential = |a: &usize, b: &usize| potential[*a].total_cmp(&potential[*b]);
+        let end = (0..potential.len())
+            .max_by(by_potential)
+            .unwrap_or_default();
+        let start = (0..potential.len())
+            .min_by(by_potential)
+            .unwrap_or_default();
+
+        let bound = best_in
+            .iter()
+            .zip(self.budget)
+            .filter_map(|(best, budget)| best.map(|(gain, _, _)| gain * *budget as f64))
+            .sum::<f64>()
+            + potential[end]
+            - potential[start];
+        let links = best_in
+            .into_iter()
+            .map(|best| best.map(|(_, from, to)| (from, to)))
+            .collect();
+        (bound, links, end, start)
+    }
+
+    /// Fit the potentials by subgradient descent on the bound of the whole
+    /// graph, keeping the potentials which gave the lowest bound.
+    ///
+    /// A node which is the best way into more segments than it is entered
+    /// itself has its potential lowered, and the other way round. The step in
+    /// round `k` is the highest coverage of a link over `k + 2`, so the steps
+    /// add up to enough to move a potential across any link, and then settle. The
+    /// first round is all zeros, so the potentials kept never give a higher
+    /// bound over the whole graph than the best link into each segment does.
+    /// The bound is a valid one whatever is kept, so the [`Self::ROUNDS`] and
+    /// step sizes only trade the time taken here against the time the search
+    /// takes.
+    fn fit_potentials(&self) -> Vec<f64> {
+        let mut potential = vec![0.0f64; self.children.len()];
+        let max_coverage = self.children.iter().flatten().map(|(_, c)| *c).max();
+        let Some(max_coverage) = max_coverage.filter(|c| *c > 0) else {
+            return potential;
+        };
+
+        let mut best = (f64::MAX, potential.clone());
+        for round in 0..Self::ROUNDS {
+            let (bound, links, end, start) = self.graph_bound(&potential);
+            if bound < best.0 {
+                best = (bound, potential.clone());
+            }
+
+            // steps out of, less steps into, each node
+            let mut excess = vec![0.0f64; potential.len()];
+            for (segment, link) in links.iter().enumerate() {
+                if let Some((from, to)) = link {
+                    excess[*from] += self.budget[segment] as f64;
+                    excess[*to] -= self.budget[segment] as f64;
+                }
+            }
+            excess[end] += 1.0;
+            excess[start] -= 1.0;
+
+            let step = max_coverage as f64 / (round + 2) as f64;
+            for (p, e) in potential.iter_mut().zip(excess) {
+                *p -= step * e.signum();
+            }
         }
+
+        best.1
     }
-}
 
-/// A recursion depth limit, so we don't hit a stack overflow
-/// and instead, abort and call another function.
-///
-/// Why is it 1000? Seemed sensible, and that's what python's is.
-const MAX_RECURSION_DEPTH: usize = 1000;
+    /// The most a path ending at `node` could go on to add to its score and
+    /// length, given its visits to each segment so far.
+    ///
+    /// Each segment it can reach can be visited as many more times as it has
+    /// left, each time through the best link into it from `node` or another
+    /// node the path can reach.
+    ///
+    /// Also gives, for each child of `node` with visits left, the coverage of
+    /// the link to it, and how much less a step to it gains than the best
+    /// step into its segment. This much less is a bound on paths through the
+    /// child, without having to traverse the graph again.
+    ///
+    /// Only paths from `start` ending on a node whose reverse complement is
+    /// not lower than `start` are bounded, as the others are the reverse
+    /// complements of paths from a lower start. Returns `None` if the path
+    /// can no longer end on any of these, or if any of the `required`
+    /// segments has visits left but can no longer be reached.
+    fn bound(
+        &mut self,
+        node: NodeIndex,
+        visits: &[usize],
+        start: NodeIndex,
+        required: &[usize],
+        children_lost: &mut Vec<(NodeIndex, i64, i64)>,
+    ) -> Option<(i64, usize)> {
+        let (children, budget, potential) = (self.children, self.budget, &self.potential);
+        // the path could end on the node itself, or any it can reach
+        let end =
+            |node: NodeIndex| (start <= reverse_index(node)).then_some(potential[node.index()]);
+        let mut end_potential = end(node);
+        // the node itself is not marked as seen, as a path may come back to it
+        self.stack.push(node);
+        while let Some(from) = self.stack.pop() {
+            for (child, coverage) in &children[from.index()] {
+                let s = child.index() / 2;
+                if visits[s] >= budget[s] {
+                    continue;
+                }
+                let gain = (coverage + potential[from.index()] - potential[child.index()]).max(0);
+                let best = self.best_in[s].get_or_insert(gain);
+                *best = (*best).max(gain);
+                if !self.seen[child.index()] {
+                    self.seen[child.index()] = true;
+                    end_potential = end_potential.max(end(*child));
+                    self.reached.push(*child);
+                    self.stack.push(*child);
+                }
+            }
+        }
 
-/// Function called by `all_paths` where a `HashMap` is supplied instead of a
-/// `HashSet` in order to keep track of how many times a segment/node has been
-/// passed in a path.
-///
-/// Should be no more stack overflows.
-fn recursive_path_finder_incl_coverage<T, U, Ix: IndexType>(
-    graph: &Graph<T, U, Directed, Ix>,
-    start_node: NodeIndex<Ix>,
-    end_node: NodeIndex<Ix>,
-    visited: &mut HashMap<NodeIndex<Ix>, usize>,
-    rel_coverage_map: &HashMap<NodeIndex<Ix>, usize>,
-    depth: usize,
-    index: usize,
-) -> Option<Result<Vec<Vec<NodeIndex<Ix>>>>> {
-    if depth > MAX_RECURSION_DEPTH {
-        eprint!(
-            "\r[-]\tRecursion depth limit ({}) exceeded in permutation {}. Switching to default path finder.",
-            MAX_RECURSION_DEPTH,
-            index + 1
+        let stranded = required
+            .iter()
+            .any(|s| visits[*s] < budget[*s] && self.best_in[*s].is_none());
+
+        children_lost.clear();
+        for (child, coverage) in &children[node.index()] {
+            if let Some(best) = self.best_in[child.index() / 2] {
+                let gain = coverage + potential[node.index()] - potential[child.index()];
+                children_lost.push((*child, *coverage, best - gain.max(0)));
+            }
+        }
+
+        let (mut score, mut length) = (
+            end_potential.unwrap_or_default() - potential[node.index()],
+            0,
         );
-        return None;
-    }
-    // if the start node is the same as the end
-    // the path is just to the end node
-    if start_node == end_node {
-        Some(Ok(vec![vec![end_node]]))
-    } else {
-        let mut paths = Vec::new();
-        for edge in graph.edges_directed(start_node, Outgoing) {
-            let next_node = edge.target();
-
-            let test = *rel_coverage_map.get(&next_node).unwrap();
-
-            if !visited.contains_key(&next_node) || *visited.get(&next_node).unwrap() != test {
-                *visited.entry(next_node).or_insert(0) += 1;
-                let descendant_paths = match recursive_path_finder_incl_coverage(
-                    graph,
-                    next_node,
-                    end_node,
-                    visited,
-                    rel_coverage_map,
-                    depth + 1,
-                    index,
-                ) {
-                    // can I get rid of this unwrap? is it safe?
-                    Some(p) => p.unwrap(),
-                    None => return None,
-                };
-                visited.remove(&next_node);
-                paths.extend(
-                    descendant_paths
-                        .into_iter()
-                        .map(|path| {
-                            let mut new_path = vec![start_node];
-                            new_path.extend(path);
-                            new_path
-                        })
-                        .collect::<Vec<_>>(),
-                )
+        for node in self.reached.drain(..) {
+            self.seen[node.index()] = false;
+            let s = node.index() / 2;
+            // both strands of a segment may be reached, but it is counted once
+            if let Some(best) = self.best_in[s].take() {
+                let left = budget[s] - visits[s];
+                score += left as i64 * best;
+                length += left;
             }
         }
-        Some(Ok(paths))
+
+        match stranded || end_potential.is_none() {
+            true => None,
+            false => Some((score, length)),
+        }
     }
 }
 
-/// A safer and more reliable alternative to `recursive_path_finder_incl_coverage` where
-/// a `HashSet` determines whether a segment/node has already been seen or not.
-fn recursive_path_finder_no_coverage<T, U, Ix: IndexType>(
-    graph: &Graph<T, U, Directed, Ix>,
-    start_node: NodeIndex<Ix>,
-    end_node: NodeIndex<Ix>,
-    visited: &mut HashSet<NodeIndex<Ix>>,
-) -> Result<Vec<Vec<NodeIndex<Ix>>>> {
+/// The most steps [`GFAdigraph::highest_coverage_path`] takes before
+/// settling for the best path found so far.
+pub const MAX_SEARCH_STEPS: usize = 1_000_000;
+
+/// Compute all paths through a [`GFAdigraph`] from a start node to an end node.
+///
+/// The search is over oriented segments, so a path entering a segment on one
+/// end always leaves it on the other. A segment may be visited (on either
+/// strand) as many times as its relative coverage in `rel_coverage_map`, or
+/// once if this is not supplied.
+///
+/// The search is iterative, so long paths cannot overflow the stack. It is
+/// exhaustive, so it stops once `max_paths` paths are found, or after
+/// [`MAX_SEARCH_STEPS`] steps, with a warning. Used by `gfatk path --between`,
+/// and in the tests, as the answer [`GFAdigraph::highest_coverage_path`] is
+/// checked against.
+pub fn all_paths(
+    gfa_graph: &GFAdigraph,
+    start_node: NodeIndex,
+    end_node: NodeIndex,
+    rel_coverage_map: Option<&HashMap<NodeIndex, usize>>,
+    max_paths: usize,
+) -> Vec<GFAPath> {
+    let graph = &gfa_graph.0;
+
+    // how many times a segment may appear in a path
+    let allowed = |node: NodeIndex| {
+        rel_coverage_map
+            .and_then(|m| m.get(&forward_index(node)).copied())
+            .unwrap_or(1)
+    };
+    let to_path = |nodes: &[NodeIndex]| {
+        GFAPath::from_segments(&nodes.iter().map(|n| graph[*n]).collect_vec())
+    };
+    // children are popped off the end, so reverse them to visit in edge order
+    let children = |node: NodeIndex| {
+        let mut children: Vec<NodeIndex> = graph
+            .edges_directed(node, Outgoing)
+            .map(|e| e.target())
+            .collect();
+        children.reverse();
+        children
+    };
+
     if start_node == end_node {
-        Ok(vec![vec![end_node]])
-    } else {
-        let mut paths = Vec::new();
-        for edge in graph.edges_directed(start_node, Outgoing) {
-            let next_node = edge.target();
-            if !visited.contains(&next_node) {
-                visited.insert(next_node);
-                let descendant_paths =
-                    recursive_path_finder_no_coverage(graph, next_node, end_node, visited)?;
-                visited.remove(&next_node);
-                paths.extend(
-                    descendant_paths
-                        .into_iter()
-                        .map(|path| {
-                            let mut new_path = vec![start_node];
-                            new_path.extend(path);
-                            new_path
-                        })
-                        .collect::<Vec<_>>(),
-                )
-            }
-        }
-        Ok(paths)
+        return vec![to_path(&[start_node])];
     }
+
+    let mut paths = Vec::new();
+    let mut steps = 0;
+    // the current path, the visits to each segment on it, and
+    // the children of each node on it which are still to be tried
+    let mut path = vec![start_node];
+    let mut visits: HashMap<NodeIndex, usize> = HashMap::new();
+    visits.insert(forward_index(start_node), 1);
+    let mut stack = vec![children(start_node)];
+
+    while let Some(to_visit) = stack.last_mut() {
+        if paths.len() >= max_paths {
+            break;
+        }
+        match to_visit.pop() {
+            Some(next_node) => {
+                steps += 1;
+                if steps > MAX_SEARCH_STEPS {
+                    warn!(
+                        "Stopped searching for paths after {} steps, with {} found.",
+                        MAX_SEARCH_STEPS,
+                        paths.len()
+                    );
+                    break;
+                }
+                let visited = visits.get(&forward_index(next_node)).copied().unwrap_or(0);
+                if visited >= allowed(next_node) {
+                    continue;
+                }
+                path.push(next_node);
+                if next_node == end_node {
+                    paths.push(to_path(&path));
+                    path.pop();
+                    continue;
+                }
+                *visits.entry(forward_index(next_node)).or_insert(0) += 1;
+                stack.push(children(next_node));
+            }
+            None => {
+                // all the children are done, so step back
+                stack.pop();
+                if let Some(node) = path.pop() {
+                    if let Some(v) = visits.get_mut(&forward_index(node)) {
+                        *v -= 1;
+                    }
+                }
+            }
+        }
+    }
+
+    paths
 }
 
 /// Returns a subgraph GFA that only contains elements with the provided segment names.
 ///
+/// Paths are cut down to the pieces through these segments. A path split
+/// into more than one piece has the number of each piece added to its name,
+/// e.g. `path1_1` and `path1_2`.
+///
 /// Taken from <https://github.com/chfi/rs-gfa-utils/blob/master/src/subgraph.rs>
 pub fn segments_subgraph<T: OptFields + Clone>(
     gfa: &GFA<usize, T>,
@@ -250,8 +1501,33 @@ pub fn segments_subgraph<T: OptFields + Clone>(
     let paths: Vec<_> = gfa
         .paths
         .iter()
-        .filter(|p| p.iter().any(|(s, _)| segment_names.contains(&s)))
-        .cloned()
+        .flat_map(|p| {
+            let steps: Vec<_> = p.iter().collect();
+            let runs = kept_runs(&steps, |(s, _)| segment_names.contains(s));
+            if runs.len() == 1 && runs[0].len() == steps.len() {
+                return vec![p.clone()];
+            }
+            let split = runs.len() > 1;
+            runs.into_iter()
+                .enumerate()
+                .map(|(index, run)| {
+                    let name = match split {
+                        true => format!("{}_{}", p.path_name.as_bstr(), index + 1).into_bytes(),
+                        false => p.path_name.clone(),
+                    };
+                    // an overlap between each step, or a single `*`
+                    let overlaps = match p.overlaps.len() + 1 == steps.len() && run.len() > 1 {
+                        true => p.overlaps[run.start..run.end - 1].to_vec(),
+                        false => vec![None],
+                    };
+                    let steps = steps[run]
+                        .iter()
+                        .map(|(s, o)| format!("{}{}", s, o))
+                        .join(",");
+                    Path::new(name, steps.into_bytes(), overlaps, p.optional.clone())
+                })
+                .collect()
+        })
         .collect();
 
     GFA {
@@ -267,127 +1543,66 @@ pub fn segments_subgraph<T: OptFields + Clone>(
 mod tests {
 
     use super::*;
+    use crate::gfa::gfa::into_digraph;
+    use crate::load::load_gfatk;
+    use petgraph::algo::tarjan_scc;
 
-    // we want to make a test graph to play with
-    // make the inner graph representation of:
-    // ./examples/mito_NC_037304.1.MZ323108.1.fasta.BOTH.HiFiMapped.bam.filtered.1k.gfa
+    // a graph and its segment names, from a test GFA
+    fn load_graph(path: &str) -> (GFAdigraph, SegmentNames) {
+        let gfa = load_gfatk(path).unwrap();
+        let (_, graph) = into_digraph(&gfa.0, &CoverageTags::default()).unwrap();
+        (graph, gfa.1)
+    }
 
+    // the graph drawn in ./tests/test_graph.gfa, taken from
+    // ./examples/mito_NC_037304.1.MZ323108.1.fasta.BOTH.HiFiMapped.bam.filtered.1k.gfa
     fn make_graph() -> GFAdigraph {
-        let mut graph = Graph::<usize, (Orientation, Orientation, Option<i64>)>::new();
-
-        // node weights are usize
-        let node0 = graph.add_node(0);
-        let node1 = graph.add_node(1);
-        let node2 = graph.add_node(2);
-        let node3 = graph.add_node(3);
-        let node4 = graph.add_node(4);
-        let node5 = graph.add_node(5);
-
-        // we create the following graph
-        //
-        //  0 <-----> 3 <-----> 1
-        //    \     / ˄ \     /
-        //      \ /   |   \ /
-        //      / \   |   / \
-        //    /     \ ˅ /     \
-        //  5 <-----> 2 <-----> 4
-        //
-
-        graph.extend_with_edges(&[
-            (
-                node0,
-                node3,
-                (Orientation::Backward, Orientation::Backward, Some(379)),
-            ),
-            (
-                node0,
-                node2,
-                (Orientation::Forward, Orientation::Backward, Some(338)),
-            ),
-            (
-                node1,
-                node3,
-                (Orientation::Backward, Orientation::Backward, Some(380)),
-            ),
-            (
-                node1,
-                node2,
-                (Orientation::Forward, Orientation::Backward, Some(374)),
-            ),
-            (
-                node2,
-                node4,
-                (Orientation::Backward, Orientation::Forward, Some(347)),
-            ),
-            (
-                node2,
-                node5,
-                (Orientation::Backward, Orientation::Forward, Some(399)),
-            ),
-            (
-                node2,
-                node1,
-                (Orientation::Forward, Orientation::Backward, Some(374)),
-            ),
-            (
-                node2,
-                node0,
-                (Orientation::Forward, Orientation::Backward, Some(338)),
-            ),
-            (
-                node3,
-                node5,
-                (Orientation::Backward, Orientation::Backward, Some(397)),
-            ),
-            (
-                node3,
-                node4,
-                (Orientation::Backward, Orientation::Backward, Some(349)),
-            ),
-            (
-                node3,
-                node1,
-                (Orientation::Forward, Orientation::Forward, Some(380)),
-            ),
-            (
-                node3,
-                node0,
-                (Orientation::Forward, Orientation::Forward, Some(379)),
-            ),
-            (
-                node4,
-                node2,
-                (Orientation::Backward, Orientation::Forward, Some(347)),
-            ),
-            (
-                node4,
-                node3,
-                (Orientation::Forward, Orientation::Forward, Some(349)),
-            ),
-            (
-                node5,
-                node2,
-                (Orientation::Backward, Orientation::Forward, Some(399)),
-            ),
-            (
-                node5,
-                node3,
-                (Orientation::Forward, Orientation::Forward, Some(397)),
-            ),
-        ]);
-
-        GFAdigraph(graph)
-    }
-
-    // there are 6 nodes in this graph
+        load_graph("./tests/test_graph.gfa").0
+    }
+
+    // the tangled graphs of ./tests/test_tangled.gfa, rings of 10 segments
+    // with 6 more links across them, on either strand.
+    fn tangled_graphs() -> Vec<(GFAdigraph, SegmentNames)> {
+        let gfa = load_gfatk("./tests/test_tangled.gfa").unwrap();
+        let (_, graph) = into_digraph(&gfa.0, &CoverageTags::default()).unwrap();
+        graph
+            .weakly_connected_components()
+            .into_iter()
+            .map(|ring| {
+                let ring = gfa.subgraph(ring);
+                let (_, graph) = into_digraph(&ring.0, &CoverageTags::default()).unwrap();
+                (graph, ring.1)
+            })
+            .collect()
+    }
+
+    // the nodes of a path through a graph
+    fn path_nodes(graph: &GFAdigraph, path: &GFAPath) -> Vec<NodeIndex> {
+        path.segments()
+            .map(|s| graph.0.node_indices().find(|n| graph.0[*n] == s).unwrap())
+            .collect()
+    }
+
+    // the coverage of each link of a path through a graph, in order
+    fn link_coverage(graph: &GFAdigraph, nodes: &[NodeIndex]) -> Vec<i64> {
+        nodes
+            .windows(2)
+            .map(|pair| graph.0[graph.0.find_edge(pair[0], pair[1]).unwrap()].unwrap())
+            .collect()
+    }
+
+    // there are 6 segments in this graph
     #[test]
     fn test_node_count() {
         let graph = make_graph();
 
         assert_eq!(graph.node_count(), 6);
+        assert_eq!(graph.0.node_count(), 12);
     }
 
     // there are 16 edges in this graph (incl +/- orientations)
+    // the links above are already closed under reverse complement,
+    // so none are added twice.
     #[test]
     fn test_edge_count() {
         let graph = make_graph();
@@ -395,114 +1610,402 @@ mod tests {
         assert_eq!(graph.edge_count(), 16);
     }
 
-    // there are two possible paths between node indexes 0 and 2
+    // a link which is not listed in both orientations gains its
+    // reverse complement.
+    #[test]
+    fn test_reverse_complement_link() {
+        let mut graph = GFAdigraph::with_capacity(2, 1);
+        let a = graph.add_segment(1);
+        let b = graph.add_segment(2);
+
+        graph.add_link(a, Orientation::Forward, b, Orientation::Backward, None);
+
+        assert_eq!(graph.edge_count(), 2);
+        // 1+ -> 2-
+        assert!(graph.0.find_edge(a, reverse_index(b)).is_some());
+        // 2+ -> 1-
+        assert!(graph.0.find_edge(b, reverse_index(a)).is_some());
+        assert_eq!(
+            graph.oriented_segment(reverse_index(a)).to_string(),
+            "1-".to_string()
+        );
+    }
+
+    // walks must respect strand: from 0- we can reach 2+ in two ways.
+    // in an orientation-blind graph 0 -> 3 -> 1 -> 2 would also be reported,
+    // but entering 3 on the reverse strand means we cannot leave towards 1.
     #[test]
     fn test_path_generation() {
         let graph = make_graph();
 
-        let paths = all_paths(&graph.0, NodeIndex::new(0), NodeIndex::new(2), None, 0).unwrap();
-
-        // there should be two paths
-        let path1: Vec<NodeIndex> = vec![NodeIndex::new(0), NodeIndex::new(2)];
-        let path2: Vec<NodeIndex> = vec![
-            NodeIndex::new(0),
-            NodeIndex::new(3),
-            NodeIndex::new(1),
-            NodeIndex::new(2),
-        ];
-        let path3: Vec<NodeIndex> = vec![
-            NodeIndex::new(0),
-            NodeIndex::new(3),
-            NodeIndex::new(4),
-            NodeIndex::new(2),
-        ];
-        let path4: Vec<NodeIndex> = vec![
-            NodeIndex::new(0),
-            NodeIndex::new(3),
-            NodeIndex::new(5),
-            NodeIndex::new(2),
-        ];
-
-        assert!(paths.contains(&path1));
-        assert!(paths.contains(&path2));
-        assert!(paths.contains(&path3));
-        assert!(paths.contains(&path4));
-    }
-
-    //
+        let start = oriented_index(NodeIndex::new(0), Orientation::Backward);
+        let end = oriented_index(NodeIndex::new(4), Orientation::Forward);
+
+        let paths = all_paths(&graph, start, end, None, usize::MAX);
+
+        let to_strings = |path: &GFAPath| {
+            path.segments()
+                .map(|s| s.to_string())
+                .collect::<Vec<String>>()
+        };
+        let paths: Vec<_> = paths.iter().map(to_strings).collect();
+
+        assert_eq!(paths.len(), 2);
+        assert!(paths.contains(&vec!["0-".into(), "3-".into(), "5-".into(), "2+".into()]));
+        assert!(paths.contains(&vec!["0-".into(), "3-".into(), "4-".into(), "2+".into()]));
+        // and only as many as asked for
+        assert_eq!(all_paths(&graph, start, end, None, 1).len(), 1);
+    }
+
+    // a segment may be passed on either strand, but counts as one visit
+    // whichever way round it is. The search is iterative, so a long chain
+    // does not overflow the stack.
+    #[test]
+    fn test_path_generation_oriented() {
+        // 0+ -> 1+ -> 0- -> 2+, a hairpin through segment 0
+        let mut graph = GFAdigraph::with_capacity(3, 4);
+        let nodes: Vec<NodeIndex> = (0..3).map(|id| graph.add_segment(id)).collect();
+        graph.add_link(
+            nodes[0],
+            Orientation::Forward,
+            nodes[1],
+            Orientation::Forward,
+            None,
+        );
+        graph.add_link(
+            nodes[1],
+            Orientation::Forward,
+            nodes[0],
+            Orientation::Backward,
+            None,
+        );
+        graph.add_link(
+            nodes[0],
+            Orientation::Backward,
+            nodes[2],
+            Orientation::Forward,
+            None,
+        );
+
+        let (start, end) = (nodes[0], nodes[2]);
+        assert!(all_paths(&graph, start, end, None, usize::MAX).is_empty());
+
+        let map: HashMap<NodeIndex, usize> = nodes.iter().map(|n| (*n, 2)).collect();
+        let paths: Vec<String> = all_paths(&graph, start, end, Some(&map), usize::MAX)
+            .iter()
+            .map(|p| p.segments().join(","))
+            .collect();
+        // the reverse complement of 1+ -> 0- is 0+ -> 1-
+        assert_eq!(paths, ["0+,1-,0-,2+", "0+,1+,0-,2+"]);
+
+        let length = 100_000;
+        let mut chain = GFAdigraph::with_capacity(length, length);
+        let nodes: Vec<NodeIndex> = (0..length).map(|id| chain.add_segment(id)).collect();
+        for pair in nodes.windows(2) {
+            chain.add_link(
+                pair[0],
+                Orientation::Forward,
+                pair[1],
+                Orientation::Forward,
+                None,
+            );
+        }
+        let paths = all_paths(&chain, nodes[0], nodes[length - 1], None, usize::MAX);
+        assert_eq!(paths.len(), 1);
+        assert_eq!(paths[0].len(), length);
+        // and not backwards, against the strand
+        assert!(all_paths(&chain, nodes[length - 1], nodes[0], None, usize::MAX).is_empty());
+    }
+
+    // the times each segment of ./tests/test_graph.gfa may be visited,
+    // 2 and 3 twice and the others once, on either strand.
+    fn copy_numbers() -> HashMap<NodeIndex, usize> {
+        let mut map = HashMap::new();
+        for (seg, cov) in [(0, 1), (1, 1), (2, 2), (3, 2), (4, 1), (5, 1)] {
+            let forward = NodeIndex::new(seg * 2);
+            map.insert(forward, cov);
+            map.insert(reverse_index(forward), cov);
+        }
+        map
+    }
+
     #[test]
     fn test_path_generation_incl_node_cov() {
+        let (graph, names) = load_graph("./tests/test_graph.gfa");
+
+        // generate the paths
+        let (path, not_in_path, _) = graph
+            .highest_coverage_path(Some(&copy_numbers()), &names, MAX_SEARCH_STEPS)
+            .unwrap();
+
+        // every segment is used, 2 and 3 twice.
+        assert_eq!(path.len(), 8);
+        assert!(not_in_path.is_empty());
+        for (seg, cov) in [(0, 1), (1, 1), (2, 2), (3, 2), (4, 1), (5, 1)] {
+            assert_eq!(path.segments().filter(|e| e.seg_id == seg).count(), cov);
+        }
+        // and each step follows a link on the correct strand.
+        assert_eq!(link_coverage(&graph, &path_nodes(&graph, &path)).len(), 7);
+    }
+
+    // the search finds a path as good as the best of every path between
+    // every pair of nodes, and reports the segments it leaves out.
+    #[test]
+    fn test_highest_coverage_path() {
+        let (graph, names) = load_graph("./tests/test_graph.gfa");
+        let map = copy_numbers();
+
+        for rel_coverage_map in [None, Some(&map)] {
+            let (path, not_in_path, header) = graph
+                .highest_coverage_path(rel_coverage_map, &names, MAX_SEARCH_STEPS)
+                .unwrap();
+            let score = score(&graph, &path);
+            assert!(score >= exhaustive(&graph, rel_coverage_map));
+            assert_eq!(header_field(&header, "coverage"), Some(score.0));
+            assert!(header.contains(&format!(":unused_segments={}/6:", not_in_path.len())));
+
+            let in_path: HashSet<usize> = path.segments().map(|s| s.seg_id).collect();
+            for seg in 0..6 {
+                assert_ne!(in_path.contains(&seg), not_in_path.contains(&seg));
+            }
+        }
+
+        for (ring, (graph, names)) in tangled_graphs().iter().enumerate() {
+            let nodes: Vec<NodeIndex> = graph.0.node_indices().collect();
+            let map: HashMap<NodeIndex, usize> = nodes[..4].iter().map(|n| (*n, 2)).collect();
+
+            for rel_coverage_map in [None, Some(&map)] {
+                let best = exhaustive(graph, rel_coverage_map);
+
+                let (path, _, _) = graph
+                    .highest_coverage_path(rel_coverage_map, names, MAX_SEARCH_STEPS)
+                    .unwrap();
+                let score = score(graph, &path);
+                // all_paths never ends on its start node, which a segment
+                // visited twice allows
+                match rel_coverage_map {
+                    None => assert_eq!(score, best, "ring {}", ring),
+                    Some(_) => assert!(score >= best, "ring {}", ring),
+                }
+
+                // stopped early, the bound holds the best path
+                let (_, _, header) = graph
+                    .highest_coverage_path(rel_coverage_map, names, 5)
+                    .unwrap();
+                let coverage_bound = header_field(&header, "coverage_bound").unwrap();
+                assert!(coverage_bound >= score.0, "ring {}", ring);
+                assert!(header_field(&header, "coverage").unwrap() <= score.0);
+            }
+        }
+    }
+
+    // the (coverage, length) of the best of every path between every pair of nodes
+    fn exhaustive(
+        graph: &GFAdigraph,
+        rel_coverage_map: Option<&HashMap<NodeIndex, usize>>,
+    ) -> (i64, usize) {
+        let nodes: Vec<NodeIndex> = graph.0.node_indices().collect();
+        nodes
+            .iter()
+            .permutations(2)
+            .flat_map(|pair| all_paths(graph, *pair[0], *pair[1], rel_coverage_map, usize::MAX))
+            .map(|path| score(graph, &path))
+            .max()
+            .unwrap()
+    }
+
+    // the (coverage, length) of a path through a graph
+    fn score(graph: &GFAdigraph, path: &GFAPath) -> (i64, usize) {
+        let coverage = link_coverage(graph, &path_nodes(graph, path));
+        (coverage.iter().sum(), path.len())
+    }
+
+    // a number in a fasta header from `highest_coverage_path`
+    fn header_field(header: &str, field: &str) -> Option<i64> {
+        header
+            .split(':')
+            .find_map(|f| f.strip_prefix(field)?.strip_prefix('='))
+            .map(|value| value.parse().unwrap())
+    }
+
+    // a ring of 200 segments with a few links across it is solved exactly,
+    // and a more tangled one is stopped, with a bound on the best path.
+    #[test]
+    fn test_highest_coverage_path_200_segments() {
+        let (graph, names) = load_graph("./tests/test_ring_200.gfa");
+        assert_eq!(graph.node_count(), 200);
+        let (path, _, header) = graph
+            .highest_coverage_path(None, &names, MAX_SEARCH_STEPS)
+            .unwrap();
+        assert_eq!(path.len(), 200);
+        assert_eq!(header_field(&header, "coverage_bound"), None);
+
+        let (graph, names) = load_graph("./tests/test_tangled_200.gfa");
+        let (_, _, header) = graph.highest_coverage_path(None, &names, 10_000).unwrap();
+        let coverage = header_field(&header, "coverage").unwrap();
+        let coverage_bound = header_field(&header, "coverage_bound").unwrap();
+        assert!(coverage <= coverage_bound);
+        // the path through every link of the ring
+        assert!(coverage >= 199 * 5);
+    }
+
+    // each circle is found once, whichever segment it is started from and
+    // on whichever strand, and closes with a link back to its start.
+    #[test]
+    fn test_circular_walks() {
         let graph = make_graph();
 
-        let mut map: HashMap<NodeIndex, usize> = HashMap::new();
-
-        // we can provide a map to say we want to visit certain nodes twice
-        map.insert(NodeIndex::new(0), 1);
-        map.insert(NodeIndex::new(1), 1);
-        map.insert(NodeIndex::new(2), 2);
-        map.insert(NodeIndex::new(3), 2);
-        map.insert(NodeIndex::new(4), 1);
-        map.insert(NodeIndex::new(5), 1);
-
-        let lookup = GFAGraphLookups(vec![
-            crate::utils::GFAGraphPair {
-                node_index: NodeIndex::new(0),
-                seg_id: 4,
-            },
-            crate::utils::GFAGraphPair {
-                node_index: NodeIndex::new(2),
-                seg_id: 6,
-            },
-            crate::utils::GFAGraphPair {
-                node_index: NodeIndex::new(5),
-                seg_id: 9,
-            },
-            crate::utils::GFAGraphPair {
-                node_index: NodeIndex::new(3),
-                seg_id: 7,
-            },
-            crate::utils::GFAGraphPair {
-                node_index: NodeIndex::new(1),
-                seg_id: 5,
-            },
-            crate::utils::GFAGraphPair {
-                node_index: NodeIndex::new(4),
-                seg_id: 8,
-            },
-        ]);
+        let walks = graph.circular_walks(None, 100).unwrap();
+        assert!(!walks.is_empty());
 
-        // generate the paths
-        let paths = graph.all_paths_all_node_pairs(&lookup, Some(&map));
-
-        // either this path
-        let longest_path1: Vec<NodeIndex> = vec![
-            NodeIndex::new(2),
-            NodeIndex::new(5),
-            NodeIndex::new(3),
-            NodeIndex::new(1),
-            NodeIndex::new(2),
-            NodeIndex::new(4),
-            NodeIndex::new(3),
-            NodeIndex::new(0),
-        ];
-
-        // or this path
-        let longest_path2: Vec<NodeIndex> = vec![
-            NodeIndex::new(2),
-            NodeIndex::new(4),
-            NodeIndex::new(3),
-            NodeIndex::new(1),
-            NodeIndex::new(2),
-            NodeIndex::new(5),
-            NodeIndex::new(3),
-            NodeIndex::new(0),
-        ];
-
-        // will be chosen
-        let both = vec![longest_path1, longest_path2];
-
-        let path = &paths.unwrap().0.iter().map(|(a, _)| *a).collect::<Vec<_>>();
-
-        assert!(both.contains(path));
+        let mut canonical = HashSet::new();
+        for (walk, coverage) in &walks {
+            let nodes = path_nodes(&graph, walk);
+            let closed: Vec<NodeIndex> = nodes.iter().chain(&nodes[..1]).copied().collect();
+            let total: i64 = link_coverage(&graph, &closed).iter().sum();
+            assert_eq!(total, *coverage);
+
+            // the rotations of the walk and its reverse complement
+            let mut forms: Vec<Vec<NodeIndex>> = Vec::new();
+            let reverse: Vec<NodeIndex> = nodes.iter().rev().map(|n| reverse_index(*n)).collect();
+            for i in 0..nodes.len() {
+                forms.push(nodes[i..].iter().chain(&nodes[..i]).copied().collect());
+                forms.push(reverse[i..].iter().chain(&reverse[..i]).copied().collect());
+            }
+            assert!(canonical.insert(forms.into_iter().min().unwrap()));
+        }
+
+        // sorted by coverage
+        assert!(walks.windows(2).all(|w| w[0].1 >= w[1].1));
+        // and limited in number
+        assert_eq!(graph.circular_walks(None, 1).unwrap().len(), 1);
+    }
+
+    // the best circles are kept when there are more than `max_walks`,
+    // even if they are found late in the search.
+    #[test]
+    fn test_circular_walks_best_kept() {
+        // a master circle a r b r, which recombines across the repeat r
+        let (graph, names) = load_graph("./tests/test_isoforms.gfa");
+
+        // a r is found first, from a
+        let walks = graph.circular_walks(None, 100).unwrap();
+        let found: Vec<(String, i64)> = walks
+            .iter()
+            .map(|(walk, coverage)| (walk.to_fasta_header(&names), *coverage))
+            .collect();
+        assert_eq!(
+            found,
+            vec![("r+,b+".to_string(), 23), ("a+,r+".to_string(), 19)]
+        );
+
+        let best = graph.circular_walks(None, 1).unwrap();
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].0.to_fasta_header(&names), "r+,b+");
+        assert_eq!(best[0].1, 23);
+
+        // the best circles are the same, however many are kept
+        let (graph, names) = &tangled_graphs()[1];
+        let walks = graph.circular_walks(None, usize::MAX).unwrap();
+        assert!(walks.len() > 2);
+        for max_walks in 1..walks.len() {
+            let best = graph.circular_walks(None, max_walks).unwrap();
+            assert_eq!(best.len(), max_walks);
+            for ((walk, coverage), (expected, expected_coverage)) in best.iter().zip(&walks) {
+                assert_eq!(walk.to_fasta_header(names), expected.to_fasta_header(names));
+                assert_eq!(coverage, expected_coverage);
+            }
+        }
+    }
+
+    #[test]
+    fn test_weakly_connected_components() {
+        let mut graph = make_graph();
+        // an isolated segment, linked only to itself
+        let node6 = graph.add_segment(6);
+        graph.add_link(
+            node6,
+            Orientation::Forward,
+            node6,
+            Orientation::Forward,
+            Some(1),
+        );
+
+        let components = graph.weakly_connected_components();
+
+        assert_eq!(components, vec![vec![0, 1, 2, 3, 4, 5], vec![6]]);
+    }
+
+    // both strands of a segment are part of the same tangle here, and
+    // the mirror image of each strongly connected component covers the same segments.
+    #[test]
+    fn test_strongly_connected_components() {
+        let graph = make_graph();
+
+        let sccs = tarjan_scc(&graph.0);
+
+        for scc in &sccs {
+            let mirror: Vec<NodeIndex> = scc.iter().map(|n| reverse_index(*n)).collect();
+            assert!(sccs
+                .iter()
+                .any(|other| graph.segment_ids(other) == graph.segment_ids(&mirror)));
+        }
+
+        // one of each mirror pair is kept
+        let canonical = graph.strongly_connected_components();
+        let segment_sets: HashSet<Vec<usize>> =
+            sccs.iter().map(|scc| graph.segment_ids(scc)).collect();
+        assert_eq!(canonical.len(), segment_sets.len());
+    }
+
+    // the iterative search finds the same components as petgraph, in parallel
+    // or not, and does not overflow the stack on a long chain.
+    #[test]
+    fn test_iterative_strongly_connected_components() {
+        let graph = make_graph();
+
+        let mut expected: Vec<Vec<usize>> = tarjan_scc(&graph.0)
+            .iter()
+            .map(|scc| {
+                let mut nodes: Vec<usize> = scc.iter().map(|n| n.index()).collect();
+                nodes.sort_unstable();
+                nodes
+            })
+            .collect();
+        expected.sort();
+        let mut found: Vec<Vec<usize>> = graph
+            .weakly_connected_nodes()
+            .iter()
+            .flat_map(|component| graph.component_sccs(component))
+            .map(|scc| {
+                let mut nodes: Vec<usize> = scc.iter().map(|n| n.index()).collect();
+                nodes.sort_unstable();
+                nodes
+            })
+            .collect();
+        found.sort();
+        assert_eq!(found, expected);
+
+        assert_eq!(
+            graph.par_strongly_connected_components(2).unwrap(),
+            graph.strongly_connected_components()
+        );
+
+        // a chain of segments, closed into a circle
+        let length = 200_000;
+        let mut chain = GFAdigraph::with_capacity(length, length);
+        let nodes: Vec<NodeIndex> = (0..length).map(|id| chain.add_segment(id)).collect();
+        for i in 0..length {
+            chain.add_link(
+                nodes[i],
+                Orientation::Forward,
+                nodes[(i + 1) % length],
+                Orientation::Forward,
+                None,
+            );
+        }
+        let sccs = chain.strongly_connected_components();
+        assert_eq!(sccs.len(), 1);
+        assert_eq!(sccs[0].len(), length);
     }
 }
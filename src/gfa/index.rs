@@ -0,0 +1,301 @@
+use crate::load::open_reader;
+use crate::logging::Stage;
+use crate::utils::reverse_complement;
+use anyhow::{bail, Context, Result};
+use bstr::ByteSlice;
+use gfa::gfa::Orientation;
+use log::warn;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+/// The extension added to a GFA path for its index, e.g. `in.gfa.gfai`.
+pub const INDEX_EXTENSION: &str = "gfai";
+
+/// Where a segment sequence is in a GFA file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SequenceLocation {
+    /// The byte offset of the first base.
+    pub offset: u64,
+    /// The length of the sequence.
+    pub length: usize,
+}
+
+/// An on-disk index of the segment sequences in a GFA, like a `.fai` for a fasta.
+///
+/// Each line of the index file is `<name>\t<length>\t<offset>`. Only plain
+/// (not compressed) GFA's can be indexed, and segments without a sequence
+/// (`*`) are left out.
+///
+/// The GFA is kept open, and every fetch seeks and reads on the same handle.
+pub struct GFAIndex {
+    /// The GFA the index is for.
+    gfa_path: PathBuf,
+    /// The open GFA, which sequences are read from.
+    file: File,
+    /// The segment names, in the order of the GFA.
+    names: Vec<Vec<u8>>,
+    /// The location of each segment sequence.
+    locations: HashMap<Vec<u8>, SequenceLocation>,
+}
+
+impl GFAIndex {
+    /// The path of the index file for a GFA.
+    pub fn index_path(gfa_path: &Path) -> PathBuf {
+        let mut path = gfa_path.as_os_str().to_owned();
+        path.push(".");
+        path.push(INDEX_EXTENSION);
+        PathBuf::from(path)
+    }
+
+    /// Open a GFA for an (as yet empty) index.
+    fn open(gfa_path: &Path) -> Result<Self> {
+        Ok(Self {
+            gfa_path: gfa_path.to_path_buf(),
+            file: File::open(gfa_path).with_context(|| format!("Could not open {:?}", gfa_path))?,
+            names: Vec::new(),
+            locations: HashMap::new(),
+        })
+    }
+
+    /// Build an index by scanning the segment lines of a GFA.
+    pub fn build(gfa_path: &Path) -> Result<Self> {
+        let mut index = Self::open(gfa_path)?;
+        let (mut reader, is_gzip) = open_reader(index.file.try_clone()?)?;
+        if is_gzip {
+            bail!(
+                "Cannot index {:?} as it is compressed. Decompress it first.",
+                gfa_path
+            );
+        }
+
+        let mut line = Vec::new();
+        let mut position: u64 = 0;
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            let line_start = position;
+            position += read as u64;
+
+            if !line.starts_with(b"S\t") {
+                continue;
+            }
+            let mut fields = line
+                .trim_end_with(|c| c == '\n' || c == '\r')
+                .split_str("\t")
+                .skip(1);
+            let (name, sequence) = match (fields.next(), fields.next()) {
+                (Some(name), Some(sequence)) => (name, sequence),
+                _ => bail!("Segment line at byte {} has no sequence field.", line_start),
+            };
+            if sequence == b"*" {
+                continue;
+            }
+            // S, the name, and two tabs come before the sequence
+            let location = SequenceLocation {
+                offset: line_start + 3 + name.len() as u64,
+                length: sequence.len(),
+            };
+            index.insert(name.to_vec(), location)?;
+        }
+
+        Ok(index)
+    }
+
+    /// Read the index of a GFA from an index file.
+    pub fn read(gfa_path: &Path, index_path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(index_path)
+            .with_context(|| format!("Could not read index {:?}", index_path))?;
+
+        let mut index = Self::open(gfa_path)?;
+        for (line_no, line) in contents.lines().enumerate() {
+            let fields: Vec<&str> = line.split('\t').collect();
+            let location = match fields[..] {
+                [_, length, offset] => length
+                    .parse::<usize>()
+                    .ok()
+                    .zip(offset.parse::<u64>().ok())
+                    .map(|(length, offset)| SequenceLocation { offset, length }),
+                _ => None,
+            };
+            match location {
+                Some(location) => index.insert(fields[0].as_bytes().to_vec(), location)?,
+                None => bail!(
+                    "Line {} of {:?} is not of the form <name>\\t<length>\\t<offset>.",
+                    line_no + 1,
+                    index_path
+                ),
+            }
+        }
+
+        Ok(index)
+    }
+
+    /// Write the index to an index file.
+    pub fn write(&self, index_path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(
+            File::create(index_path)
+                .with_context(|| format!("Could not create index {:?}", index_path))?,
+        );
+        for name in &self.names {
+            let location = self.locations[name];
+            writeln!(
+                writer,
+                "{}\t{}\t{}",
+                name.as_bstr(),
+                location.length,
+                location.offset
+            )?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Read the index of a GFA if it exists and is up to date, otherwise
+    /// build it and write it next to the GFA.
+    pub fn load_or_build(gfa_path: &Path) -> Result<Self> {
+        let index_path = Self::index_path(gfa_path);
+
+        let modified = |path: &Path| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+        let is_current = match (modified(&index_path), modified(gfa_path)) {
+            (Some(index_time), Some(gfa_time)) => index_time >= gfa_time,
+            _ => false,
+        };
+        if is_current {
+            return Self::read(gfa_path, &index_path);
+        }
+
+        let stage = Stage::start(format!("Indexing segment sequences of {:?}", gfa_path));
+        let index = Self::build(gfa_path)?;
+        stage.finish();
+        // not being able to write the index is not fatal, it is only a cache
+        if let Err(e) = index.write(&index_path) {
+            warn!("Could not write index: {:#}", e);
+        }
+        Ok(index)
+    }
+
+    /// Add a segment to the index.
+    fn insert(&mut self, name: Vec<u8>, location: SequenceLocation) -> Result<()> {
+        if self.locations.insert(name.clone(), location).is_some() {
+            bail!("Segment {} is in the GFA more than once.", name.as_bstr());
+        }
+        self.names.push(name);
+        Ok(())
+    }
+
+    /// The number of indexed segments.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether no segments are indexed.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// The segment names, in the order of the GFA.
+    pub fn names(&self) -> impl Iterator<Item = &[u8]> {
+        self.names.iter().map(|n| n.as_slice())
+    }
+
+    /// Where a segment sequence is in the GFA.
+    pub fn location(&self, name: &[u8]) -> Result<SequenceLocation> {
+        self.locations
+            .get(name)
+            .copied()
+            .with_context(|| format!("Segment {} is not in the index.", name.as_bstr()))
+    }
+
+    /// Fetch the sequence of a segment.
+    pub fn sequence(&self, name: &[u8]) -> Result<Vec<u8>> {
+        let length = self.location(name)?.length;
+        self.subsequence(name, 0, length, Orientation::Forward)
+    }
+
+    /// Fetch the bases `start..end` (0-based, end exclusive) of a segment's
+    /// forward strand, reverse complemented if `orientation` is backward.
+    pub fn subsequence(
+        &self,
+        name: &[u8],
+        start: usize,
+        end: usize,
+        orientation: Orientation,
+    ) -> Result<Vec<u8>> {
+        let location = self.location(name)?;
+        if start > end || end > location.length {
+            bail!(
+                "Region {}-{} is outside of segment {} (length {}).",
+                start,
+                end,
+                name.as_bstr(),
+                location.length
+            );
+        }
+
+        // reads and seeks through a shared reference to the one open file
+        let mut file = &self.file;
+        file.seek(SeekFrom::Start(location.offset + start as u64))?;
+        let mut sequence = vec![0; end - start];
+        file.read_exact(&mut sequence).with_context(|| {
+            format!(
+                "Could not read segment {} from {:?}",
+                name.as_bstr(),
+                self.gfa_path
+            )
+        })?;
+
+        match orientation {
+            Orientation::Forward => Ok(sequence),
+            Orientation::Backward => Ok(reverse_complement(&sequence)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_gfa_index() {
+        let gfa_path = Path::new("./tests/test_names.gfa");
+        let index = GFAIndex::build(gfa_path).unwrap();
+
+        assert_eq!(index.len(), 3);
+        assert_eq!(index.sequence(b"utg000002l").unwrap(), b"TCAAGG");
+        assert_eq!(
+            index
+                .subsequence(b"utg000003l", 1, 4, Orientation::Backward)
+                .unwrap(),
+            b"CAA"
+        );
+        assert!(index
+            .subsequence(b"utg000003l", 4, 8, Orientation::Forward)
+            .is_err());
+        assert!(index.sequence(b"utg000009l").is_err());
+
+        // the index can be written and read back
+        let index_path =
+            std::env::temp_dir().join(format!("gfatk_index_{}.gfai", std::process::id()));
+        index.write(&index_path).unwrap();
+        let read = GFAIndex::read(gfa_path, &index_path).unwrap();
+        std::fs::remove_file(index_path).unwrap();
+
+        assert_eq!(
+            read.names().collect::<Vec<_>>(),
+            index.names().collect::<Vec<_>>()
+        );
+        assert_eq!(
+            read.location(b"utg000001l").unwrap(),
+            index.location(b"utg000001l").unwrap()
+        );
+
+        // compressed GFA's cannot be indexed
+        assert!(GFAIndex::build(Path::new("./tests/test_linear.gfa.gz")).is_err());
+    }
+}
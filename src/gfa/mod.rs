@@ -1,16 +1,86 @@
-use ::gfa::writer::write_gfa;
-use ::gfa::{gfa::GFA, optfields::OptionalFields};
+use crate::utils::get_option_string;
+use ::gfa::gfa::{SegmentId, GFA};
+use bstr::ByteSlice;
+use optfields::OptionalTags;
 
+/// A module where the CIGAR strings of link overlaps are parsed.
+pub mod cigar;
+/// A module where the copy number of each segment is estimated from its coverage.
+pub mod copy_number;
+/// A module where segment and link coverage are read from their tags.
+pub mod coverage;
 /// A module where all the methods of GFA manipulations are defined.
+#[allow(clippy::module_inception)]
 pub mod gfa;
 /// A module where a GFA is coerced into a petgraph `Graph` structure, with associated methods.
 pub mod graph;
+/// A module where the segment sequences of a GFA are indexed on disk, so single sequences can be read without loading the GFA.
+pub mod index;
+/// A module where the original segment names of a GFA are interned, so the rest of `gfatk` can use `usize` ID's.
+pub mod names;
+/// A module where the optional fields of a line are read, keeping the subtype of integer arrays, which the gfa crate drops.
+pub mod optfields;
+/// A module where only the topology of a GFA is streamed in, without sequences.
+pub mod topology;
+/// A module where walk (`W`, GFA 1.1) and jump (`J`, GFA 1.2) lines are parsed, as the gfa crate drops them.
+pub mod walk;
 
 /// Writes a GFA to a string.
 ///
-/// Modified from function of the same name in gfa crate.
-pub fn gfa_string(gfa: &GFA<usize, OptionalFields>) -> String {
+/// Modified from `write_gfa` in the gfa crate, which writes `B` and `H`
+/// optional fields which cannot be read back in.
+pub fn gfa_string<N: SegmentId>(gfa: &GFA<N, OptionalTags>) -> String {
+    // the optional fields of a line, preceded by a tab
+    let optional = |options: &OptionalTags| match options.is_empty() {
+        true => String::new(),
+        false => format!("\t{}", get_option_string(options)),
+    };
     let mut result = String::new();
-    write_gfa(gfa, &mut result);
+
+    result += "H";
+    if let Some(version) = &gfa.header.version {
+        result += &format!("\tVN:Z:{}", version.as_bstr());
+    }
+    result += &format!("{}\n", optional(&gfa.header.optional));
+
+    for segment in &gfa.segments {
+        result += &format!(
+            "S\t{}\t{}{}\n",
+            segment.name.display(),
+            segment.sequence.as_bstr(),
+            optional(&segment.optional)
+        );
+    }
+
+    for link in &gfa.links {
+        result += &format!(
+            "L\t{}\t{}\t{}\t{}\t{}{}\n",
+            link.from_segment.display(),
+            link.from_orient,
+            link.to_segment.display(),
+            link.to_orient,
+            link.overlap.as_bstr(),
+            optional(&link.optional)
+        );
+    }
+
+    for path in &gfa.paths {
+        let overlaps: Vec<String> = path
+            .overlaps
+            .iter()
+            .map(|o| match o {
+                Some(o) => o.to_string(),
+                None => "*".into(),
+            })
+            .collect();
+        result += &format!(
+            "P\t{}\t{}\t{}{}\n",
+            path.path_name.as_bstr(),
+            path.segment_names.as_bstr(),
+            overlaps.join(","),
+            optional(&path.optional)
+        );
+    }
+
     result
 }
@@ -0,0 +1,259 @@
+use crate::error::GfatkError;
+use crate::gfa::graph::OrientedSegment;
+use crate::gfa::walk::WalksJumps;
+use anyhow::Result;
+use bstr::{BStr, ByteSlice};
+use gfa::gfa::{Containment, Link, Path, Segment, GFA};
+use gfa::optfields::OptFields;
+use std::collections::HashMap;
+
+/// An interned table of segment names.
+///
+/// Internally, segments are referred to by a `usize` ID which is their index
+/// in this table. Any segment name (`12`, `s1`, `utg000001l`) can be read in,
+/// and is written back out unchanged.
+#[derive(Clone, Debug, Default)]
+pub struct SegmentNames {
+    ids: HashMap<Vec<u8>, usize>,
+    names: Vec<Vec<u8>>,
+}
+
+impl SegmentNames {
+    /// Return the ID of a segment name, adding it to the table if it is new.
+    pub fn intern(&mut self, name: &[u8]) -> usize {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.ids.insert(name.to_vec(), id);
+        self.names.push(name.to_vec());
+        id
+    }
+
+    /// Return the ID of a segment name, if it is in the table.
+    pub fn id(&self, name: &[u8]) -> Option<usize> {
+        self.ids.get(name).copied()
+    }
+
+    /// Return the ID of a segment name, or an error if it is not in the GFA.
+    pub fn try_id(&self, name: &str) -> Result<usize> {
+        self.id(name.as_bytes())
+            .ok_or_else(|| GfatkError::MissingSegment(name.into()).into())
+    }
+
+    /// Return the name of a segment ID.
+    ///
+    /// Panics if the ID did not come from this table.
+    pub fn name(&self, id: usize) -> &BStr {
+        self.names[id].as_bstr()
+    }
+
+    /// Format an oriented segment with its original name, e.g. `utg000001l+`.
+    pub fn oriented(&self, segment: OrientedSegment) -> String {
+        format!("{}{}", self.name(segment.seg_id), segment.orientation)
+    }
+
+    /// Join the names of some segment ID's with a comma.
+    pub fn join(&self, ids: &[usize]) -> String {
+        ids.iter()
+            .map(|id| self.name(*id).to_string())
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// The number of segment names in the table.
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    /// Whether the table is empty.
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    /// Convert a GFA with segment names of any form to one with `usize`
+    /// segment ID's, returning the table of the original names.
+    ///
+    /// Segments are numbered in the order they appear. Names only seen in
+    /// links, containments or paths are also added, so that missing segments
+    /// can be reported later on.
+    pub fn intern_gfa<T: OptFields + Clone>(gfa: &GFA<Vec<u8>, T>) -> (GFA<usize, T>, Self) {
+        let mut names = Self::default();
+
+        let segments = gfa
+            .segments
+            .iter()
+            .map(|s| Segment {
+                name: names.intern(&s.name),
+                sequence: s.sequence.clone(),
+                optional: s.optional.clone(),
+            })
+            .collect();
+
+        let links = gfa
+            .links
+            .iter()
+            .map(|l| Link {
+                from_segment: names.intern(&l.from_segment),
+                from_orient: l.from_orient,
+                to_segment: names.intern(&l.to_segment),
+                to_orient: l.to_orient,
+                overlap: l.overlap.clone(),
+                optional: l.optional.clone(),
+            })
+            .collect();
+
+        let containments = gfa
+            .containments
+            .iter()
+            .map(|c| Containment {
+                container_name: names.intern(&c.container_name),
+                container_orient: c.container_orient,
+                contained_name: names.intern(&c.contained_name),
+                contained_orient: c.contained_orient,
+                pos: c.pos,
+                overlap: c.overlap.clone(),
+                optional: c.optional.clone(),
+            })
+            .collect();
+
+        let paths = gfa
+            .paths
+            .iter()
+            .map(|p| {
+                let steps = p
+                    .iter()
+                    .map(|(name, o)| format!("{}{}", names.intern(name), o))
+                    .collect::<Vec<String>>()
+                    .join(",");
+                Path::new(
+                    p.path_name.clone(),
+                    steps.into_bytes(),
+                    p.overlaps.clone(),
+                    p.optional.clone(),
+                )
+            })
+            .collect();
+
+        (
+            GFA {
+                header: gfa.header.clone(),
+                segments,
+                links,
+                containments,
+                paths,
+            },
+            names,
+        )
+    }
+
+    /// Convert the segment names of walk and jump lines to `usize` ID's, adding
+    /// any new names to the table.
+    pub fn intern_walks(&mut self, walks_jumps: &WalksJumps<Vec<u8>>) -> WalksJumps<usize> {
+        walks_jumps.map_segments(|name| self.intern(name))
+    }
+
+    /// Convert the `usize` segment ID's of walk and jump lines from this table
+    /// back to the original segment names.
+    pub fn name_walks(&self, walks_jumps: &WalksJumps<usize>) -> WalksJumps<Vec<u8>> {
+        walks_jumps.map_segments(|id| self.names[*id].clone())
+    }
+
+    /// Convert a GFA with `usize` segment ID's from this table back to one
+    /// with the original segment names.
+    pub fn name_gfa<T: OptFields + Clone>(&self, gfa: &GFA<usize, T>) -> GFA<Vec<u8>, T> {
+        let name = |id: usize| self.names[id].clone();
+
+        let segments = gfa
+            .segments
+            .iter()
+            .map(|s| Segment {
+                name: name(s.name),
+                sequence: s.sequence.clone(),
+                optional: s.optional.clone(),
+            })
+            .collect();
+
+        let links = gfa
+            .links
+            .iter()
+            .map(|l| Link {
+                from_segment: name(l.from_segment),
+                from_orient: l.from_orient,
+                to_segment: name(l.to_segment),
+                to_orient: l.to_orient,
+                overlap: l.overlap.clone(),
+                optional: l.optional.clone(),
+            })
+            .collect();
+
+        let containments = gfa
+            .containments
+            .iter()
+            .map(|c| Containment {
+                container_name: name(c.container_name),
+                container_orient: c.container_orient,
+                contained_name: name(c.contained_name),
+                contained_orient: c.contained_orient,
+                pos: c.pos,
+                overlap: c.overlap.clone(),
+                optional: c.optional.clone(),
+            })
+            .collect();
+
+        let paths = gfa
+            .paths
+            .iter()
+            .map(|p| {
+                let steps = p
+                    .iter()
+                    .map(|(id, o)| format!("{}{}", self.name(id), o))
+                    .collect::<Vec<String>>()
+                    .join(",");
+                Path::new(
+                    p.path_name.clone(),
+                    steps.into_bytes(),
+                    p.overlaps.clone(),
+                    p.optional.clone(),
+                )
+            })
+            .collect();
+
+        GFA {
+            header: gfa.header.clone(),
+            segments,
+            links,
+            containments,
+            paths,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::gfa::gfa_string;
+    use crate::gfa::optfields::OptionalTags;
+    use crate::load::load_gfa;
+
+    #[test]
+    fn test_round_trip_names() {
+        let gfa: GFA<Vec<u8>, OptionalTags> = load_gfa("./tests/test_names.gfa").unwrap();
+        let (interned, names) = SegmentNames::intern_gfa(&gfa);
+
+        assert_eq!(names.len(), 3);
+        assert_eq!(names.id(b"utg000002l"), Some(1));
+        assert_eq!(interned.links[0].to_segment, 1);
+        assert_eq!(
+            interned.paths[0].iter().collect::<Vec<_>>(),
+            vec![
+                (0, gfa::gfa::Orientation::Forward),
+                (1, gfa::gfa::Orientation::Backward),
+                (2, gfa::gfa::Orientation::Forward)
+            ]
+        );
+
+        assert_eq!(gfa_string(&names.name_gfa(&interned)), gfa_string(&gfa));
+    }
+}
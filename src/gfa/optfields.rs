@@ -0,0 +1,100 @@
+use gfa::optfields::{OptField, OptFieldVal, OptFields};
+use std::ops::{Deref, DerefMut};
+
+/// The subtypes of an integer `B` array, from `int8_t` (`c`) to `uint32_t` (`I`).
+const INT_ARRAY_SUBTYPES: &[u8] = b"cCsSiI";
+
+/// The optional fields of a GFA line.
+///
+/// As the gfa crate's `OptionalFields`, but the subtype of each integer
+/// `B` array (e.g. the `i` of `B:i,1,2`) is kept, as the gfa crate reads
+/// only the values, so the array can be written back as it was read. It
+/// derefs to the fields, so they can be read and changed as a `Vec`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OptionalTags {
+    fields: Vec<OptField>,
+    /// The tag and subtype of each integer array.
+    int_array_subtypes: Vec<([u8; 2], u8)>,
+}
+
+impl OptionalTags {
+    /// The subtype an integer array was read with, if it is one.
+    pub fn int_array_subtype(&self, tag: &[u8]) -> Option<u8> {
+        self.int_array_subtypes
+            .iter()
+            .find(|(t, _)| t == tag)
+            .map(|(_, subtype)| *subtype)
+    }
+}
+
+impl From<Vec<OptField>> for OptionalTags {
+    fn from(fields: Vec<OptField>) -> Self {
+        Self {
+            fields,
+            int_array_subtypes: Vec::new(),
+        }
+    }
+}
+
+impl Deref for OptionalTags {
+    type Target = Vec<OptField>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.fields
+    }
+}
+
+impl DerefMut for OptionalTags {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.fields
+    }
+}
+
+impl OptFields for OptionalTags {
+    fn get_field(&self, tag: &[u8]) -> Option<&OptField> {
+        self.fields.iter().find(|o| o.tag == tag)
+    }
+
+    fn fields(&self) -> &[OptField] {
+        &self.fields
+    }
+
+    fn parse<T>(input: T) -> Self
+    where
+        T: IntoIterator,
+        T::Item: AsRef<[u8]>,
+    {
+        let mut tags = Self::default();
+        for field in input {
+            let field = field.as_ref();
+            let Some(option) = OptField::parse(field) else {
+                continue;
+            };
+            // <TAG>:B:<subtype>,<values>
+            if let (OptFieldVal::BInt(_), Some(subtype)) = (&option.value, field.get(5)) {
+                if INT_ARRAY_SUBTYPES.contains(subtype) {
+                    tags.int_array_subtypes.push((option.tag, *subtype));
+                }
+            }
+            tags.fields.push(option);
+        }
+        tags
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_int_array_subtype() {
+        let tags = OptionalTags::parse(["bi:B:i,1,2", "bs:B:s,-1", "bf:B:f,0.5", "ll:f:30"]);
+
+        assert_eq!(tags.len(), 4);
+        assert_eq!(tags.int_array_subtype(b"bi"), Some(b'i'));
+        assert_eq!(tags.int_array_subtype(b"bs"), Some(b's'));
+        assert_eq!(tags.int_array_subtype(b"bf"), None);
+        assert_eq!(tags.int_array_subtype(b"ll"), None);
+    }
+}
@@ -0,0 +1,252 @@
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::graph::GFAdigraph;
+use crate::gfa::names::SegmentNames;
+use crate::gfa::walk::{Jump, Walk, WalksJumps};
+use crate::load::decompressed_reader;
+use crate::logging::Stage;
+use anyhow::{bail, Context, Result};
+use bstr::ByteSlice;
+use gfa::gfa::Orientation;
+use gfa::optfields::{OptFieldVal, OptFields, OptionalFields};
+use log::info;
+use petgraph::graph::NodeIndex;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, Read};
+use std::path::Path;
+
+/// The topology of a GFA: segment names and lengths, links, paths and walks,
+/// but no sequences.
+///
+/// It is read by streaming the GFA once, building the directed graph as it
+/// goes, so the memory used does not depend on the sequence length of the
+/// GFA. It is used by subcommands which never touch sequence (`SSC` and
+/// `bubbles`). Link overlaps are not kept, so those which stitch sequences
+/// together (`path`, `linear` and `overlap`) still load the whole GFA.
+pub struct GFATopology {
+    /// The original segment names.
+    pub names: SegmentNames,
+    /// The sequence length of each segment, indexed by segment ID.
+    pub lengths: Vec<usize>,
+    /// The directed graph, as from [`crate::gfa::gfa::into_digraph`].
+    pub graph: GFAdigraph,
+    /// The forward strand node of each segment ID.
+    pub graph_indices: Vec<NodeIndex>,
+    /// The paths (`P` lines).
+    pub paths: Vec<TopologyPath>,
+    /// Walk (`W`) and jump (`J`) lines.
+    pub walks_jumps: WalksJumps<usize>,
+}
+
+/// The name and oriented segments of a path (`P` line).
+pub type TopologyPath = (Vec<u8>, Vec<(usize, Orientation)>);
+
+/// Split a GFA line into its tab separated fields, or fail with the line number.
+fn fields(line: &[u8], count: usize, line_no: usize) -> Result<Vec<&[u8]>> {
+    let fields: Vec<&[u8]> = line.split_str("\t").collect();
+    if fields.len() < count {
+        bail!(GfatkError::Parse {
+            line: line_no,
+            message: format!(
+                "it has {} fields, but at least {} are needed.",
+                fields.len(),
+                count
+            ),
+        });
+    }
+    Ok(fields)
+}
+
+/// Parse a `+`/`-` orientation, or fail with the line number.
+fn orientation(field: &[u8], line_no: usize) -> Result<Orientation> {
+    Orientation::from_bytes_plus_minus(field).ok_or_else(|| {
+        GfatkError::Parse {
+            line: line_no,
+            message: format!("invalid orientation {}.", field.as_bstr()),
+        }
+        .into()
+    })
+}
+
+impl GFATopology {
+    /// Stream the topology of a GFA from a (possibly compressed) file,
+    /// reading link coverage from the `coverage_tags`.
+    pub fn from_path<P: AsRef<Path>>(path: P, coverage_tags: &CoverageTags) -> Result<Self> {
+        let path = path.as_ref();
+        let file = File::open(path).with_context(|| format!("Could not open {:?}", path))?;
+        Self::from_reader(file, coverage_tags)
+            .with_context(|| format!("Failed to parse GFA from path: {:?}", path))
+    }
+
+    /// Stream the topology of a GFA from a (possibly compressed) reader,
+    /// reading link coverage from the `coverage_tags`.
+    pub fn from_reader<R: Read>(reader: R, coverage_tags: &CoverageTags) -> Result<Self> {
+        let stage = Stage::start("Streaming GFA topology into a directed graph");
+        let mut reader = decompressed_reader(reader)?;
+
+        let mut names = SegmentNames::default();
+        let mut graph = GFAdigraph::with_capacity(0, 0);
+        let mut lengths: Vec<Option<usize>> = Vec::new();
+        let mut paths = Vec::new();
+        let mut walks_jumps = WalksJumps::default();
+
+        // intern a segment name, adding its nodes to the graph if it is new
+        let mut segment_id = |names: &mut SegmentNames, graph: &mut GFAdigraph, name: &[u8]| {
+            let id = names.intern(name);
+            if id == lengths.len() {
+                graph.add_segment(id);
+                lengths.push(None);
+            }
+            id
+        };
+
+        let mut line = Vec::new();
+        let mut line_no = 0;
+        // lengths are borrowed by `segment_id`, so are set after
+        let mut segment_lengths: Vec<(usize, usize)> = Vec::new();
+        let mut segment_coverage = HashMap::new();
+
+        loop {
+            line.clear();
+            let read = reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                break;
+            }
+            line_no += 1;
+            let line = line.trim_end_with(|c| c == '\n' || c == '\r');
+
+            match line.first() {
+                Some(b'S') => {
+                    let f = fields(line, 3, line_no)?;
+                    let id = segment_id(&mut names, &mut graph, f[1]);
+                    // the tags are only needed for the length, or to derive edge coverage
+                    let optional = match f[2] == b"*" || coverage_tags.derive_edges {
+                        true => OptionalFields::parse(f[3..].iter()),
+                        false => OptionalFields::default(),
+                    };
+                    let length = match f[2] {
+                        b"*" => match optional.get_field(b"LN").map(|ln| &ln.value) {
+                            Some(OptFieldVal::Int(ln)) => *ln as usize,
+                            _ => 0,
+                        },
+                        sequence => sequence.len(),
+                    };
+                    if let Some(coverage) = coverage_tags.segment_coverage(&optional, length) {
+                        segment_coverage.insert(id, coverage);
+                    }
+                    segment_lengths.push((id, length));
+                }
+                Some(b'L') => {
+                    let f = fields(line, 6, line_no)?;
+                    let from = segment_id(&mut names, &mut graph, f[1]);
+                    let to = segment_id(&mut names, &mut graph, f[3]);
+                    let optional = OptionalFields::parse(f[6..].iter());
+                    graph.add_link(
+                        NodeIndex::new(from * 2),
+                        orientation(f[2], line_no)?,
+                        NodeIndex::new(to * 2),
+                        orientation(f[4], line_no)?,
+                        coverage_tags.link_coverage(&optional),
+                    );
+                }
+                Some(b'P') => {
+                    let f = fields(line, 3, line_no)?;
+                    let mut steps = Vec::new();
+                    for step in f[2].split_str(",") {
+                        let (name, orient) = step.split_at(step.len().saturating_sub(1));
+                        steps.push((
+                            segment_id(&mut names, &mut graph, name),
+                            orientation(orient, line_no)?,
+                        ));
+                    }
+                    paths.push((f[1].to_vec(), steps));
+                }
+                Some(b'W') => {
+                    let walk: Walk<Vec<u8>> =
+                        Walk::parse_line(line).map_err(|e| GfatkError::Parse {
+                            line: line_no,
+                            message: format!("not a valid walk: {}", e),
+                        })?;
+                    let walk = walk.map_segments(|name| segment_id(&mut names, &mut graph, name));
+                    walks_jumps.walks.push(walk);
+                }
+                Some(b'J') => {
+                    let jump: Jump<Vec<u8>> =
+                        Jump::parse_line(line).map_err(|e| GfatkError::Parse {
+                            line: line_no,
+                            message: format!("not a valid jump: {}", e),
+                        })?;
+                    let jump = jump.map_segments(|name| segment_id(&mut names, &mut graph, name));
+                    walks_jumps.jumps.push(jump);
+                }
+                // headers, containments, and anything else are not needed
+                _ => (),
+            }
+        }
+
+        for (id, length) in segment_lengths {
+            lengths[id] = Some(length);
+        }
+        if coverage_tags.derive_edges {
+            graph.derive_edge_coverage(&segment_coverage);
+        }
+        let lengths = lengths
+            .into_iter()
+            .enumerate()
+            .map(|(id, length)| {
+                length.ok_or_else(|| GfatkError::MissingSegment(names.name(id).to_string()).into())
+            })
+            .collect::<Result<Vec<usize>>>()?;
+
+        info!(
+            "Read {} segments and {} edges.",
+            lengths.len(),
+            graph.edge_count()
+        );
+        stage.finish();
+
+        Ok(Self {
+            graph_indices: (0..lengths.len())
+                .map(|id| NodeIndex::new(id * 2))
+                .collect(),
+            names,
+            lengths,
+            graph,
+            paths,
+            walks_jumps,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::gfa::gfa::into_digraph;
+    use crate::load::load_gfatk;
+
+    #[test]
+    fn test_topology_matches_full_load() {
+        let coverage_tags = CoverageTags::default();
+        let topology = GFATopology::from_path("./tests/test_walks.gfa", &coverage_tags).unwrap();
+        let gfa = load_gfatk("./tests/test_walks.gfa").unwrap();
+        let (_, graph) = into_digraph(&gfa.0, &coverage_tags).unwrap();
+
+        assert_eq!(topology.graph.node_count(), graph.node_count());
+        assert_eq!(topology.graph.edge_count(), graph.edge_count());
+        assert_eq!(topology.lengths, vec![6; 5]);
+        assert_eq!(topology.walks_jumps.walks.len(), 4);
+        assert_eq!(
+            topology.graph.strongly_connected_components(),
+            graph.strongly_connected_components()
+        );
+    }
+
+    #[test]
+    fn test_topology_missing_segment() {
+        let gfa = b"S\t1\tACGT\nL\t1\t+\t2\t+\t0M\n";
+        let topology = GFATopology::from_reader(&gfa[..], &CoverageTags::default());
+        assert!(topology.is_err());
+    }
+}
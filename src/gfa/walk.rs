@@ -0,0 +1,416 @@
+use crate::gfa::optfields::OptionalTags;
+use crate::utils::get_option_field_string;
+use bstr::ByteSlice;
+use gfa::gfa::{Orientation, SegmentId};
+use gfa::optfields::OptFields;
+use gfa::parser::ParseFieldError;
+use std::fmt;
+use std::ops::Range;
+
+/// A walk (`W` line, GFA 1.1), the path of a haplotype through the graph.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Walk<N> {
+    /// The sample the haplotype is from.
+    pub sample_id: Vec<u8>,
+    /// The haplotype index, e.g. 1 or 2 in a diploid.
+    pub hap_index: usize,
+    /// The sequence (e.g. chromosome or contig) the walk is on.
+    pub seq_id: Vec<u8>,
+    /// Start of the walk on the sequence, if known.
+    pub seq_start: Option<usize>,
+    /// End of the walk on the sequence, if known.
+    pub seq_end: Option<usize>,
+    /// The oriented segments of the walk.
+    pub steps: Vec<(N, Orientation)>,
+    /// The optional fields of the walk.
+    pub optional: OptionalTags,
+}
+
+/// A jump (`J` line, GFA 1.2), joining two segments across a gap rather
+/// than an overlap.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jump<N> {
+    /// The segment the jump starts from.
+    pub from_segment: N,
+    /// The orientation of the start segment.
+    pub from_orient: Orientation,
+    /// The segment the jump ends on.
+    pub to_segment: N,
+    /// The orientation of the end segment.
+    pub to_orient: Orientation,
+    /// The estimated length of the gap, if known (`*` otherwise).
+    pub distance: Option<i64>,
+    /// The optional fields of the jump.
+    pub optional: OptionalTags,
+}
+
+/// The walk and jump lines of a GFA, which the `gfa` crate does not parse.
+#[derive(Clone, Debug)]
+pub struct WalksJumps<N> {
+    /// Walk (`W`) lines.
+    pub walks: Vec<Walk<N>>,
+    /// Jump (`J`) lines.
+    pub jumps: Vec<Jump<N>>,
+}
+
+impl<N> Default for WalksJumps<N> {
+    fn default() -> Self {
+        Self {
+            walks: Vec::new(),
+            jumps: Vec::new(),
+        }
+    }
+}
+
+/// Parse a field which may be `*`.
+fn parse_optional_number<T: std::str::FromStr>(field: &[u8]) -> Result<Option<T>, ParseFieldError> {
+    if field == b"*" {
+        return Ok(None);
+    }
+    std::str::from_utf8(field)?
+        .parse::<T>()
+        .map(Some)
+        .map_err(|_| ParseFieldError::ParseFromStringError)
+}
+
+/// Parse a `+`/`-` orientation.
+fn parse_orientation(field: Option<&[u8]>) -> Result<Orientation, ParseFieldError> {
+    Orientation::parse_error(Orientation::from_bytes_plus_minus(
+        field.ok_or(ParseFieldError::MissingFields)?,
+    ))
+}
+
+impl<N: SegmentId> Walk<N> {
+    /// Parse a tab delimited `W` line.
+    pub fn parse_line(line: &[u8]) -> Result<Self, ParseFieldError> {
+        let mut fields = line.split_str(b"\t");
+        if fields.next() != Some(b"W") {
+            return Err(ParseFieldError::InvalidField("W"));
+        }
+        let mut next = || fields.next().ok_or(ParseFieldError::MissingFields);
+
+        let sample_id = next()?.to_vec();
+        let hap_index = std::str::from_utf8(next()?)?.parse::<usize>()?;
+        let seq_id = next()?.to_vec();
+        let seq_start = parse_optional_number(next()?)?;
+        let seq_end = parse_optional_number(next()?)?;
+        let steps = Self::parse_steps(next()?)?;
+        let optional = OptionalTags::parse(fields);
+
+        Ok(Self {
+            sample_id,
+            hap_index,
+            seq_id,
+            seq_start,
+            seq_end,
+            steps,
+            optional,
+        })
+    }
+
+    /// Parse a walk of oriented segments, e.g. `>s1<s2>s3`.
+    fn parse_steps(walk: &[u8]) -> Result<Vec<(N, Orientation)>, ParseFieldError> {
+        let mut steps = Vec::new();
+        let mut rest = walk;
+        while let Some(orient) = rest.first() {
+            let orient = Orientation::parse_error(Orientation::from_bytes_gt_ln([*orient]))?;
+            let end = rest[1..]
+                .iter()
+                .position(|c| *c == b'>' || *c == b'<')
+                .map(|p| p + 1)
+                .unwrap_or(rest.len());
+            let name = N::parse_id(&rest[1..end]).ok_or(N::ERROR)?;
+            steps.push((name, orient));
+            rest = &rest[end..];
+        }
+        if steps.is_empty() {
+            return Err(ParseFieldError::InvalidField("Walk"));
+        }
+        Ok(steps)
+    }
+}
+
+impl<N> Walk<N> {
+    /// The haplotype of the walk, named as in PanSN, e.g. `HG002#1#chr1`.
+    pub fn haplotype(&self) -> String {
+        format!(
+            "{}#{}#{}",
+            self.sample_id.as_bstr(),
+            self.hap_index,
+            self.seq_id.as_bstr()
+        )
+    }
+
+    /// Convert the segments of the walk.
+    pub fn map_segments<M, F: FnMut(&N) -> M>(&self, mut f: F) -> Walk<M> {
+        Walk {
+            sample_id: self.sample_id.clone(),
+            hap_index: self.hap_index,
+            seq_id: self.seq_id.clone(),
+            seq_start: self.seq_start,
+            seq_end: self.seq_end,
+            steps: self.steps.iter().map(|(s, o)| (f(s), *o)).collect(),
+            optional: self.optional.clone(),
+        }
+    }
+}
+
+impl<N: SegmentId> Jump<N> {
+    /// Parse a tab delimited `J` line.
+    pub fn parse_line(line: &[u8]) -> Result<Self, ParseFieldError> {
+        let mut fields = line.split_str(b"\t");
+        if fields.next() != Some(b"J") {
+            return Err(ParseFieldError::InvalidField("J"));
+        }
+
+        let from_segment = N::parse_next(&mut fields)?;
+        let from_orient = parse_orientation(fields.next())?;
+        let to_segment = N::parse_next(&mut fields)?;
+        let to_orient = parse_orientation(fields.next())?;
+        let distance = parse_optional_number(fields.next().ok_or(ParseFieldError::MissingFields)?)?;
+        let optional = OptionalTags::parse(fields);
+
+        Ok(Self {
+            from_segment,
+            from_orient,
+            to_segment,
+            to_orient,
+            distance,
+            optional,
+        })
+    }
+}
+
+impl<N> Jump<N> {
+    /// Convert the segments of the jump.
+    pub fn map_segments<M, F: FnMut(&N) -> M>(&self, mut f: F) -> Jump<M> {
+        Jump {
+            from_segment: f(&self.from_segment),
+            from_orient: self.from_orient,
+            to_segment: f(&self.to_segment),
+            to_orient: self.to_orient,
+            distance: self.distance,
+            optional: self.optional.clone(),
+        }
+    }
+}
+
+impl<N> WalksJumps<N> {
+    /// Convert the segments of all the walks and jumps.
+    pub fn map_segments<M, F: FnMut(&N) -> M>(&self, mut f: F) -> WalksJumps<M> {
+        WalksJumps {
+            walks: self.walks.iter().map(|w| w.map_segments(&mut f)).collect(),
+            jumps: self.jumps.iter().map(|j| j.map_segments(&mut f)).collect(),
+        }
+    }
+}
+
+/// The runs of consecutive steps which are all kept, as ranges of step indices.
+pub fn kept_runs<T>(steps: &[T], keep: impl Fn(&T) -> bool) -> Vec<Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for (index, step) in steps.iter().enumerate() {
+        match (keep(step), start) {
+            (true, None) => start = Some(index),
+            (false, Some(s)) => {
+                runs.push(s..index);
+                start = None;
+            }
+            _ => (),
+        }
+    }
+    if let Some(s) = start {
+        runs.push(s..steps.len());
+    }
+    runs
+}
+
+impl Walk<usize> {
+    /// The pieces of the walk through these segments, split wherever the walk
+    /// leaves them.
+    ///
+    /// A walk which stays in the segments is kept as it is. Otherwise each
+    /// piece gets its own start and end on the sequence, from `step_spans`,
+    /// which gives the start and end of each step relative to the start of
+    /// the walk. These are `*` if the walk's start or the spans are unknown.
+    pub fn subgraph<F>(&self, segment_ids: &[usize], step_spans: F) -> Vec<Self>
+    where
+        F: Fn(&Self) -> Option<Vec<(usize, usize)>>,
+    {
+        let runs = kept_runs(&self.steps, |(s, _)| segment_ids.contains(s));
+        if runs.len() == 1 && runs[0].len() == self.steps.len() {
+            return vec![self.clone()];
+        }
+        let spans = self.seq_start.and_then(|_| step_spans(self));
+
+        runs.into_iter()
+            .map(|run| {
+                let (seq_start, seq_end) = match (self.seq_start, &spans) {
+                    (Some(start), Some(spans)) => (
+                        Some(start + spans[run.start].0),
+                        Some(start + spans[run.end - 1].1),
+                    ),
+                    _ => (None, None),
+                };
+                Walk {
+                    seq_start,
+                    seq_end,
+                    steps: self.steps[run].to_vec(),
+                    ..self.clone()
+                }
+            })
+            .collect()
+    }
+}
+
+impl WalksJumps<usize> {
+    /// Cut the walks down to the pieces through these segments (see
+    /// [`Walk::subgraph`]), and keep the jumps between two of them.
+    pub fn subgraph<F>(&self, segment_ids: &[usize], step_spans: F) -> Self
+    where
+        F: Fn(&Walk<usize>) -> Option<Vec<(usize, usize)>>,
+    {
+        Self {
+            walks: self
+                .walks
+                .iter()
+                .flat_map(|w| w.subgraph(segment_ids, &step_spans))
+                .collect(),
+            jumps: self
+                .jumps
+                .iter()
+                .filter(|j| {
+                    segment_ids.contains(&j.from_segment) && segment_ids.contains(&j.to_segment)
+                })
+                .cloned()
+                .collect(),
+        }
+    }
+}
+
+/// Write the optional fields of a line, each preceded by a tab.
+fn write_optional_fields(f: &mut fmt::Formatter, optional: &OptionalTags) -> fmt::Result {
+    for field in optional.fields() {
+        let subtype = optional.int_array_subtype(&field.tag);
+        write!(f, "\t{}", get_option_field_string(field, subtype))?;
+    }
+    Ok(())
+}
+
+impl<N: SegmentId> fmt::Display for Walk<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let position = |p: Option<usize>| p.map(|p| p.to_string()).unwrap_or("*".into());
+        write!(
+            f,
+            "W\t{}\t{}\t{}\t{}\t{}\t",
+            self.sample_id.as_bstr(),
+            self.hap_index,
+            self.seq_id.as_bstr(),
+            position(self.seq_start),
+            position(self.seq_end)
+        )?;
+        for (segment, orient) in &self.steps {
+            orient.write_gt_ln(f)?;
+            write!(f, "{}", segment.display())?;
+        }
+        write_optional_fields(f, &self.optional)
+    }
+}
+
+impl<N: SegmentId> fmt::Display for Jump<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "J\t{}\t{}\t{}\t{}\t{}",
+            self.from_segment.display(),
+            self.from_orient,
+            self.to_segment.display(),
+            self.to_orient,
+            self.distance.map(|d| d.to_string()).unwrap_or("*".into())
+        )?;
+        write_optional_fields(f, &self.optional)
+    }
+}
+
+impl<N: SegmentId> fmt::Display for WalksJumps<N> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for walk in &self.walks {
+            writeln!(f, "{}", walk)?;
+        }
+        for jump in &self.jumps {
+            writeln!(f, "{}", jump)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_parse_walk() {
+        let line = b"W\tHG002\t1\tchr1\t0\t18\t>s1<s2>s3\tWT:Z:x";
+        let walk: Walk<Vec<u8>> = Walk::parse_line(line).unwrap();
+
+        assert_eq!(walk.haplotype(), "HG002#1#chr1");
+        assert_eq!(walk.seq_end, Some(18));
+        assert_eq!(
+            walk.steps,
+            vec![
+                (b"s1".to_vec(), Orientation::Forward),
+                (b"s2".to_vec(), Orientation::Backward),
+                (b"s3".to_vec(), Orientation::Forward)
+            ]
+        );
+        assert_eq!(walk.to_string().as_bytes(), line);
+
+        let walk: Walk<Vec<u8>> = Walk::parse_line(b"W\tHG002\t1\tchr1\t*\t*\t>s1").unwrap();
+        assert_eq!(walk.seq_start, None);
+
+        assert!(Walk::<Vec<u8>>::parse_line(b"W\tHG002\t1\tchr1\t*\t*\ts1").is_err());
+        assert!(Walk::<Vec<u8>>::parse_line(b"W\tHG002\tone\tchr1\t*\t*\t>s1").is_err());
+    }
+
+    #[test]
+    fn test_parse_jump() {
+        let line = b"J\ts1\t+\ts2\t-\t100\tSC:i:1";
+        let jump: Jump<Vec<u8>> = Jump::parse_line(line).unwrap();
+
+        assert_eq!(jump.to_orient, Orientation::Backward);
+        assert_eq!(jump.distance, Some(100));
+        assert_eq!(jump.to_string().as_bytes(), line);
+
+        let jump: Jump<Vec<u8>> = Jump::parse_line(b"J\ts1\t+\ts2\t-\t*").unwrap();
+        assert_eq!(jump.distance, None);
+    }
+
+    #[test]
+    fn test_walk_subgraph() {
+        let walk: Walk<usize> =
+            Walk::parse_line(b"W\tHG002\t1\tchr1\t10\t30\t>0>1>2>3\tWT:Z:x").unwrap();
+        // four steps of length 6, each overlapping the one before by 2
+        let spans = |_: &Walk<usize>| Some(vec![(0, 6), (4, 10), (8, 14), (12, 18)]);
+
+        assert_eq!(kept_runs(&walk.steps, |(s, _)| *s != 2), vec![0..2, 3..4]);
+
+        let pieces = walk.subgraph(&[0, 1, 3], spans);
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(
+            pieces[0].to_string(),
+            "W\tHG002\t1\tchr1\t10\t20\t>0>1\tWT:Z:x"
+        );
+        assert_eq!(
+            pieces[1].to_string(),
+            "W\tHG002\t1\tchr1\t22\t28\t>3\tWT:Z:x"
+        );
+
+        // a walk which stays in the subgraph is kept as it is
+        assert_eq!(walk.subgraph(&[0, 1, 2, 3], spans), vec![walk.clone()]);
+        assert!(walk.subgraph(&[4], spans).is_empty());
+
+        let pieces = walk.subgraph(&[1], |_| None);
+        assert_eq!(pieces[0].to_string(), "W\tHG002\t1\tchr1\t*\t*\t>1\tWT:Z:x");
+    }
+}
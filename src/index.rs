@@ -0,0 +1,38 @@
+use std::path::PathBuf;
+
+use crate::error::GfatkError;
+use crate::gfa::index::GFAIndex;
+use crate::utils;
+use anyhow::{bail, Result};
+use log::info;
+
+/// Index the segment sequences of a GFA, writing `<GFA>.gfai`.
+///
+/// The index is then used by `gfatk fasta --segments`, which only needs a few
+/// sequences. The other subcommands do not use it yet.
+///
+/// For example:
+/// ```bash
+/// gfatk index in.gfa
+/// ```
+pub fn index(matches: &clap::ArgMatches) -> Result<()> {
+    let gfa_file = matches
+        .get_one::<PathBuf>("GFA")
+        .expect("GFA is a required argument");
+
+    if !utils::is_gfa_path(gfa_file) {
+        bail!(GfatkError::Input("Input is not a GFA.".into()))
+    }
+
+    let index = GFAIndex::build(gfa_file)?;
+    let index_path = GFAIndex::index_path(gfa_file);
+    index.write(&index_path)?;
+
+    info!(
+        "Indexed {} segment sequences to {:?}.",
+        index.len(),
+        index_path
+    );
+
+    Ok(())
+}
@@ -1,31 +1,48 @@
 use std::path::PathBuf;
 
-use crate::gfa::gfa::GFAtk;
-use crate::gfa::graph::{segments_subgraph, GFAdigraph};
-use crate::load::{load_gfa, load_gfa_stdin};
-use crate::path::{parse_path, CLIOpt};
-use crate::utils::{self, GFAGraphLookups};
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::{into_digraph, GFAtk};
+use crate::gfa::graph::{GFAdigraph, MAX_SEARCH_STEPS};
+use crate::load::{load_gfatk, load_gfatk_stdin};
+use crate::path::{link_overlaps, Circular};
+use crate::utils;
 use anyhow::{bail, Context, Result};
+use log::{info, warn};
 use petgraph::algo::is_cyclic_directed;
+use petgraph::graph::NodeIndex;
+use std::collections::{HashMap, HashSet};
 
 /// Force a linear representation of the GFA.
 ///
-/// This function finds *all* legal paths through a GFA, and returns the longest path, with the highest cumulative edge coverage.
+/// This function searches the legal paths through a GFA for the one with the highest cumulative edge coverage, and on a tie the longest. It is a branch and bound search, so paths which cannot beat the best found so far are not followed, and if the search runs too long the best path found so far is returned, with a warning. The number of segments and links left out of the path are added to the fasta header.
 ///
 /// If the `-i` option is included, node coverages are taken into account, and paths are created with nodes appearing in the final path the number of times they relatively occur according to coverage information.
 ///
+/// If the `-c` option is included, every distinct circular walk through the GFA is printed instead (e.g. the master circle and sub-circles of a plant mitochondrion). The overlap at the junction of each is trimmed, and they can be rotated to start at a segment or sequence motif.
+///
 /// For example:
 /// ```bash
 /// # simple
 /// gfatk linear in.gfa > out.fasta
 /// # account for node coverage
 /// gfatk -i linear in.gfa > out.fasta
+/// # every circular isoform
+/// gfatk linear -i -c in.gfa > isoforms.fasta
 /// ```
 pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
     // read in path and parse gfa
     let gfa_file = matches.get_one::<PathBuf>("GFA");
     let include_node_coverage = matches.get_flag("include-node-coverage");
     let evaluate_subgraphs = matches.get_flag("evaluate-subgraphs");
+    let max_isoforms = match matches.get_flag("circular") {
+        true => Some(
+            *matches
+                .get_one::<usize>("max-isoforms")
+                .expect("defaulted by clap"),
+        ),
+        false => None,
+    };
     // this unwrap_or is redundant.
     let node_threshold = *matches
         .get_one::<usize>("node-threshold")
@@ -33,26 +50,27 @@ pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
 
     let gfa: GFAtk = match gfa_file {
         Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
-                }
-                None => bail!("Could not read file."),
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => GFAtk(load_gfa_stdin(std::io::stdin().lock())?),
-            false => bail!("No input from STDIN. Run `gfatk linear -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk linear -h` for help.".into()
+            )),
         },
     };
 
+    // isoforms which do not contain the start are printed as they are
+    let circular = Circular::from_matches(matches, &gfa.1, false)?;
+
     // load gfa into graph structure
-    let (graph_indices, gfa_graph) = gfa.into_digraph()?;
+    let coverage_tags = CoverageTags::from_matches(matches)?;
+    let (graph_indices, gfa_graph) = into_digraph(&gfa.0, &coverage_tags)?;
+    gfa_graph.ensure_edge_coverage(&gfa.1, &coverage_tags)?;
+    let node_coverage_tags = include_node_coverage.then_some(&coverage_tags);
 
     // if we have only one node (segment) then all we can do
     // is print the sequence
@@ -60,22 +78,22 @@ pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
     // makes sense as you can't have a path of length 1.
     if gfa_graph.node_count() == 1 {
         // as we would in `gfatk fasta`
-        eprintln!("[+]\tOnly a single segment detected. Printing sequence and exiting.");
+        info!("Only a single segment detected. Printing sequence and exiting.");
         gfa.print_sequences(None)?;
         return Ok(());
     }
 
     // check how many subgraphs there are
-    let subgraphs = gfa_graph.weakly_connected_components(graph_indices.clone())?;
+    let subgraphs = gfa_graph.weakly_connected_components();
 
     // Warn user if there is more than one subgraph
     if subgraphs.len() > 1 {
-        eprintln!(
-            "[-]\tThe input GFA has multiple subgraphs ({}).",
+        warn!(
+            "The input GFA has multiple subgraphs ({}).",
             subgraphs.len()
         );
         if !evaluate_subgraphs {
-            eprintln!("[-]\tYou did not specify the `-e` option, so only the first subgraph will be linearised.");
+            warn!("You did not specify the `-e` option, so only the first subgraph will be linearised.");
         }
     }
 
@@ -87,8 +105,9 @@ pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
                 // have to make the extra allocation here.
                 let gfa = gfa.clone();
                 // make the new GFA
-                let subgraph_gfa = GFAtk(segments_subgraph(&gfa.0, id_set.to_vec()));
-                let (graph_indices_subgraph, subgraph) = subgraph_gfa.into_digraph()?;
+                let subgraph_gfa = gfa.subgraph(id_set.to_vec());
+                let (graph_indices_subgraph, subgraph) =
+                    into_digraph(&subgraph_gfa.0, &coverage_tags)?;
 
                 // it can be useful to see here whether the subgraph is cyclic
                 let is_circular = is_cyclic_directed(&subgraph.0);
@@ -100,8 +119,8 @@ pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
                         Some(format!(" subgraph-{}:is_circular-{}", index, is_circular));
                     subgraph_gfa.print_sequences(subgraph_index_header)?;
                 } else if subgraph.node_count() > node_threshold {
-                    eprintln!(
-                        "[-]\tDetected {} nodes in a subgraph. Skipping.",
+                    warn!(
+                        "Detected {} nodes in a subgraph. Skipping.",
                         subgraph.node_count()
                     );
                     continue;
@@ -111,10 +130,12 @@ pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
                         Some(format!(" subgraph-{}:is_circular-{}", index, is_circular));
                     linear_inner(
                         subgraph_gfa,
-                        include_node_coverage,
+                        node_coverage_tags,
                         graph_indices_subgraph,
                         subgraph,
                         subgraph_index_header,
+                        max_isoforms,
+                        &circular,
                     )?;
                 }
             }
@@ -126,7 +147,15 @@ pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
                     gfa_graph.node_count()
                 );
             }
-            linear_inner(gfa, include_node_coverage, graph_indices, gfa_graph, None)?;
+            linear_inner(
+                gfa,
+                node_coverage_tags,
+                graph_indices,
+                gfa_graph,
+                None,
+                max_isoforms,
+                &circular,
+            )?;
         }
     }
 
@@ -136,35 +165,39 @@ pub fn linear(matches: &clap::ArgMatches) -> Result<()> {
 /// Reusable function to call on subgraphs in a GFA if necessary.
 fn linear_inner(
     gfa: GFAtk,
-    include_node_coverage: bool,
-    graph_indices: GFAGraphLookups,
+    node_coverage_tags: Option<&CoverageTags>,
+    graph_indices: HashMap<usize, NodeIndex>,
     gfa_graph: GFAdigraph,
     subgraph_index_header: Option<String>,
+    max_isoforms: Option<usize>,
+    circular: &Circular,
 ) -> Result<()> {
     // don't evaluate the coverage if we don't care about it
-    let rel_coverage_map = match include_node_coverage {
-        true => Some(gfa.gen_cov_hash(&graph_indices)?),
-        false => None,
+    let rel_coverage_map = match node_coverage_tags {
+        Some(coverage_tags) => Some(gfa.gen_cov_hash(&graph_indices, &gfa_graph, coverage_tags)?),
+        None => None,
     };
 
+    if let Some(max_isoforms) = max_isoforms {
+        return circular_isoforms(
+            gfa,
+            rel_coverage_map,
+            gfa_graph,
+            subgraph_index_header,
+            max_isoforms,
+            circular,
+        );
+    }
+
     let (chosen_path, segments_not_in_path, mut fasta_header) =
-        gfa_graph.all_paths_all_node_pairs(&graph_indices, rel_coverage_map.as_ref())?;
+        gfa_graph.highest_coverage_path(rel_coverage_map.as_ref(), &gfa.1, MAX_SEARCH_STEPS)?;
 
     // add in subgraph index header
     fasta_header += &subgraph_index_header.clone().unwrap_or("".to_string());
 
-    let mut chosen_path_as_string = String::new();
-
-    for (node, orientation) in chosen_path {
-        let node_id = graph_indices.node_index_to_seg_id(node)?;
-        chosen_path_as_string += &format!("{}{},", node_id, orientation);
-    }
-    // remove last comma
-    chosen_path_as_string.pop();
-
-    let (path, link_map) = parse_path(&chosen_path_as_string, CLIOpt::String, &gfa)?;
+    let link_map = link_overlaps(&gfa)?;
 
-    gfa.from_path_cli(path, link_map, "linear", Some(&fasta_header))?;
+    gfa.from_path_cli(chosen_path, link_map, "linear", Some(&fasta_header), None)?;
 
     // print the rest of the fasta headers
     // print the rest of the segments
@@ -175,7 +208,7 @@ fn linear_inner(
                     if seg.name == segment {
                         println!(
                             ">{}{}\n{}",
-                            segment,
+                            gfa.1.name(segment),
                             subgraph_index_header.clone().unwrap_or("".into()),
                             std::str::from_utf8(&seg.sequence)
                                 .with_context(|| format!("Malformed UTF8: {:?}", &seg.sequence))?
@@ -188,3 +221,63 @@ fn linear_inner(
 
     Ok(())
 }
+
+/// Print each of the circular walks through a (sub)graph as a fasta record,
+/// called with `gfatk linear --circular`.
+///
+/// The header has the index of the isoform, its path, and its cumulative edge coverage.
+/// The overlap of the link closing each walk is trimmed from its start.
+fn circular_isoforms(
+    gfa: GFAtk,
+    rel_coverage_map: Option<HashMap<NodeIndex, usize>>,
+    gfa_graph: GFAdigraph,
+    subgraph_index_header: Option<String>,
+    max_isoforms: usize,
+    circular: &Circular,
+) -> Result<()> {
+    let walks = gfa_graph.circular_walks(rel_coverage_map.as_ref(), max_isoforms)?;
+
+    if walks.is_empty() {
+        warn!("There are no circular walks through this graph.");
+        return Ok(());
+    }
+    info!("Found {} circular isoforms.", walks.len());
+
+    let link_map = link_overlaps(&gfa)?;
+
+    let mut in_walks = HashSet::new();
+    for (index, (walk, coverage)) in walks.into_iter().enumerate() {
+        in_walks.extend(walk.segments().map(|s| s.seg_id));
+
+        let fasta_header = format!(
+            "gfatk_circular:isoform={}:path={}:coverage={}{}",
+            index + 1,
+            walk.to_fasta_header(&gfa.1),
+            coverage,
+            subgraph_index_header.clone().unwrap_or_default()
+        );
+        gfa.from_path_cli(
+            walk,
+            link_map.clone(),
+            "linear",
+            Some(&fasta_header),
+            Some(circular),
+        )?;
+    }
+
+    let not_in_walks = gfa
+        .0
+        .segments
+        .iter()
+        .filter(|s| !in_walks.contains(&s.name))
+        .count();
+    if not_in_walks > 0 {
+        warn!(
+            "{} of {} segments are not in any circular isoform.",
+            not_in_walks,
+            gfa.0.segments.len()
+        );
+    }
+
+    Ok(())
+}
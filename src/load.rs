@@ -2,29 +2,133 @@
 // taken from:
 // https://github.com/chfi/rs-gfa-utils/blob/2065b001d107ee9f5d7abe04d65ab82193fc5904/src/commands.rs
 
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::GFAtk;
+use crate::gfa::index::GFAIndex;
+use crate::gfa::topology::GFATopology;
+use crate::gfa::walk::{Jump, Walk, WalksJumps};
+use crate::logging::Stage;
 use anyhow::{Context, Result};
 use bstr::io::*;
+use flate2::bufread::MultiGzDecoder;
 use gfa::{
     gfa::{SegmentId, GFA},
     optfields::OptFields,
-    parser::{GFAParser, ParseError},
+    parser::{error::ParserTolerance, GFAParser, ParseError, ParseFieldError},
 };
-use std::io::{BufReader, Read, StdinLock};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, StdinLock};
+
+/// The first two bytes of a gzip (and so also a bgzip) file.
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
 
 /// Iterate over the byte lines of a file.
-pub fn byte_lines_iter<'a, R: Read + 'a>(reader: R) -> Box<dyn Iterator<Item = Vec<u8>> + 'a> {
-    Box::new(BufReader::new(reader).byte_lines().map(|l| l.unwrap()))
+pub fn byte_lines_iter<'a, R: Read + 'a>(
+    reader: R,
+) -> Box<dyn Iterator<Item = std::io::Result<Vec<u8>>> + 'a> {
+    Box::new(BufReader::new(reader).byte_lines())
+}
+
+/// Wrap a reader, decompressing it on the fly if it is gzip or bgzip compressed.
+///
+/// Compression is detected from the magic bytes, not the file extension.
+pub fn decompressed_reader<'a, R: Read + 'a>(reader: R) -> std::io::Result<Box<dyn BufRead + 'a>> {
+    Ok(open_reader(reader)?.0)
+}
+
+/// As [`decompressed_reader`], but also return whether the input was compressed.
+pub fn open_reader<'a, R: Read + 'a>(reader: R) -> std::io::Result<(Box<dyn BufRead + 'a>, bool)> {
+    let mut reader = BufReader::new(reader);
+    let is_gzip = reader.fill_buf()?.starts_with(&GZIP_MAGIC);
+
+    if is_gzip {
+        // bgzip files are a series of concatenated gzip members
+        Ok((Box::new(BufReader::new(MultiGzDecoder::new(reader))), true))
+    } else {
+        Ok((Box::new(reader), false))
+    }
+}
+
+/// Parse a GFA line by line from a (possibly compressed) reader.
+///
+/// As in `GFAParser::parse_file`, empty lines and unknown line types are skipped.
+/// Walk (`W`) and jump (`J`) lines are parsed separately.
+fn parse_gfa_reader<N, T, R>(reader: R) -> Result<(GFA<N, T>, WalksJumps<N>), GfatkError>
+where
+    N: SegmentId,
+    T: OptFields,
+    R: Read,
+{
+    let parser = GFAParser::new();
+    let lines = decompressed_reader(reader)?.byte_lines();
+
+    let mut gfa = GFA::new();
+    let mut walks_jumps = WalksJumps::default();
+
+    let invalid_line = |line_no: usize, err: ParseFieldError, line: &[u8]| GfatkError::Parse {
+        line: line_no,
+        message: format!("{}: {}", err, String::from_utf8_lossy(line)),
+    };
+
+    for (line_no, line) in lines.enumerate() {
+        let line_no = line_no + 1;
+        let line = line?;
+        if line.starts_with(b"W\t") {
+            let walk = Walk::parse_line(&line).map_err(|e| invalid_line(line_no, e, &line))?;
+            walks_jumps.walks.push(walk);
+            continue;
+        }
+        if line.starts_with(b"J\t") {
+            let jump = Jump::parse_line(&line).map_err(|e| invalid_line(line_no, e, &line))?;
+            walks_jumps.jumps.push(jump);
+            continue;
+        }
+        match parser.parse_gfa_line(line.as_ref()) {
+            Ok(parsed) => gfa.insert_line(parsed),
+            Err(err) if err.can_safely_continue(&ParserTolerance::Safe) => (),
+            Err(ParseError::InvalidLine(err, _)) => return Err(invalid_line(line_no, err, &line)),
+            Err(ParseError::IOError(err)) => return Err(err.into()),
+            Err(err) => {
+                return Err(GfatkError::Parse {
+                    line: line_no,
+                    message: err.to_string(),
+                })
+            }
+        };
+    }
+
+    Ok((gfa, walks_jumps))
 }
 
 /// Given a path, load the GFA into a `GFA` struct.
+///
+/// The file may be gzip or bgzip compressed. Walk (`W`) and jump (`J`) lines
+/// are dropped, see [`load_gfatk`].
 pub fn load_gfa<N, T, P>(path: P) -> Result<GFA<N, T>>
 where
     N: SegmentId,
     T: OptFields,
     P: AsRef<std::path::Path>,
 {
-    let parser = GFAParser::new();
-    let gfa = parser.parse_file(path.as_ref()).with_context(|| {
+    Ok(load_gfa_walks(path)?.0)
+}
+
+/// Given a path, load the GFA into a `GFA` struct, along with its walk (`W`)
+/// and jump (`J`) lines.
+///
+/// The file may be gzip or bgzip compressed.
+fn load_gfa_walks<N, T, P>(path: P) -> Result<(GFA<N, T>, WalksJumps<N>)>
+where
+    N: SegmentId,
+    T: OptFields,
+    P: AsRef<std::path::Path>,
+{
+    let parse = || -> Result<(GFA<N, T>, WalksJumps<N>), GfatkError> {
+        let file = File::open(path.as_ref())?;
+        parse_gfa_reader(file)
+    };
+    let gfa = parse().with_context(|| {
         format!(
             "Failed to parse GFA from path: {:?}",
             path.as_ref().as_os_str()
@@ -33,33 +137,87 @@ where
     Ok(gfa)
 }
 
+/// Given a path, load the GFA into a [`GFAtk`], keeping any segment names and
+/// walk (`W`) and jump (`J`) lines.
+///
+/// The file may be gzip or bgzip compressed.
+pub fn load_gfatk<P: AsRef<std::path::Path>>(path: P) -> Result<GFAtk> {
+    let stage = Stage::start("Reading GFA");
+    let (gfa, walks_jumps) = load_gfa_walks(path)?;
+    let gfa = GFAtk::from_gfa(gfa, walks_jumps)?;
+    stage.finish();
+    Ok(gfa)
+}
+
 // take input from stdin, instead of a file.
 // we'll lock on to it, saves a bit of code repitition
 
 /// If the file is coming from STDIN, this function reads a GFA in.
-pub fn load_gfa_stdin<N, T>(stdin: StdinLock) -> Result<GFA<N, T>, ParseError>
+///
+/// The input may be gzip or bgzip compressed. Walk (`W`) and jump (`J`) lines
+/// are dropped, see [`load_gfatk_stdin`].
+pub fn load_gfa_stdin<N, T>(stdin: StdinLock) -> Result<GFA<N, T>, GfatkError>
 where
     N: SegmentId,
     T: OptFields,
 {
-    let parser = GFAParser::new();
-    let lines = BufReader::new(stdin).byte_lines();
+    Ok(parse_gfa_reader(stdin)?.0)
+}
 
-    let mut gfa = GFA::new();
+/// Given a path, stream the topology of a GFA (no sequences) into a [`GFATopology`].
+///
+/// See [`GFATopology::from_path`].
+pub fn load_gfa_topology<P: AsRef<std::path::Path>>(
+    path: P,
+    coverage_tags: &CoverageTags,
+) -> Result<GFATopology> {
+    GFATopology::from_path(path, coverage_tags)
+}
 
-    for line in lines {
-        let line = line?;
-        // if this not added then
-        if line.is_empty() {
-            continue;
-        }
-        match parser.parse_gfa_line(line.as_ref()) {
-            Ok(parsed) => gfa.insert_line(parsed),
-            // I don't have access to the .tolerance field...
-            // Err(err) if err.can_safely_continue(&parser.tolerance) => (),
-            Err(err) => return Err(err),
-        };
-    }
+/// Given a path, load the sequence index of a GFA into a [`GFAIndex`],
+/// building it if there is no up to date `.gfai` next to the GFA.
+///
+/// See [`GFAIndex::load_or_build`].
+pub fn load_gfa_index<P: AsRef<std::path::Path>>(path: P) -> Result<GFAIndex> {
+    GFAIndex::load_or_build(path.as_ref())
+}
 
+/// If the file is coming from STDIN, this function reads a GFA into a [`GFAtk`].
+///
+/// The input may be gzip or bgzip compressed.
+pub fn load_gfatk_stdin(stdin: StdinLock) -> Result<GFAtk> {
+    let stage = Stage::start("Reading GFA from STDIN");
+    let (gfa, walks_jumps) = parse_gfa_reader(stdin)?;
+    let gfa = GFAtk::from_gfa(gfa, walks_jumps)?;
+    stage.finish();
     Ok(gfa)
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+    use crate::gfa::optfields::OptionalTags;
+    use flate2::{write::GzEncoder, Compression};
+    use std::io::Write;
+
+    #[test]
+    fn test_gzip_detection() {
+        let plain = std::fs::read("./tests/test_linear.gfa").unwrap();
+
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&plain).unwrap();
+        let mut compressed = encoder.finish().unwrap();
+        // bgzip style, a second gzip member
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(b"S\t14\tACGT\n").unwrap();
+        compressed.extend(encoder.finish().unwrap());
+
+        let (gfa, _): (GFA<usize, OptionalTags>, _) = parse_gfa_reader(&compressed[..]).unwrap();
+        assert_eq!(gfa.segments.len(), 4);
+        assert_eq!(gfa.links.len(), 6);
+
+        let (gfa, _): (GFA<usize, OptionalTags>, _) = parse_gfa_reader(&plain[..]).unwrap();
+        assert_eq!(gfa.segments.len(), 3);
+    }
+}
@@ -0,0 +1,194 @@
+use anyhow::{Context, Result};
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::io::Write;
+use std::time::{Instant, SystemTime};
+
+/// The format of the log lines written to STDERR.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LogFormat {
+    /// Tab separated, for people: time, elapsed time, peak memory, then the message.
+    Text,
+    /// One JSON object per line, for machines.
+    Json,
+}
+
+/// The logger behind the `log` macros used throughout `gfatk`.
+///
+/// Every line carries a timestamp, the time elapsed since `gfatk` started,
+/// and the peak memory used so far. In the text format, the message is
+/// marked `[+]` for progress, `[-]` for warnings, `[!]` for errors, and
+/// `[.]` for debugging output.
+pub struct Logger {
+    /// The most verbose level which is written.
+    level: LevelFilter,
+    /// Text or JSON lines.
+    format: LogFormat,
+    /// When the logger was set up, as a proxy for when `gfatk` started.
+    start: Instant,
+}
+
+impl Logger {
+    /// Set up the global logger.
+    pub fn init(level: LevelFilter, format: LogFormat) -> Result<()> {
+        log::set_boxed_logger(Box::new(Self {
+            level,
+            format,
+            start: Instant::now(),
+        }))
+        .context("Could not set up the logger")?;
+        log::set_max_level(level);
+        Ok(())
+    }
+
+    /// The log level from the number of `-q` and `-v` flags.
+    ///
+    /// Progress is shown by default, `-q` shows only warnings and `-qq` only
+    /// errors. `-v` adds debugging output, and `-vv` everything.
+    pub fn level(quiet: u8, verbose: u8) -> LevelFilter {
+        match verbose as i8 - quiet as i8 {
+            i8::MIN..=-2 => LevelFilter::Error,
+            -1 => LevelFilter::Warn,
+            0 => LevelFilter::Info,
+            1 => LevelFilter::Debug,
+            _ => LevelFilter::Trace,
+        }
+    }
+
+    /// Format a record as a line (without the newline).
+    fn format_record(&self, record: &Record) -> String {
+        let time = humantime::format_rfc3339_seconds(SystemTime::now());
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let peak_memory = peak_memory_kb();
+
+        match self.format {
+            LogFormat::Text => {
+                let marker = match record.level() {
+                    Level::Error => "[!]",
+                    Level::Warn => "[-]",
+                    Level::Info => "[+]",
+                    Level::Debug | Level::Trace => "[.]",
+                };
+                let peak_memory = peak_memory
+                    .map(|kb| format!("{:.1}MB", kb as f64 / 1024.0))
+                    .unwrap_or_else(|| "-".into());
+                format!(
+                    "{}\t{:.2}s\t{}\t{}\t{}",
+                    time,
+                    elapsed,
+                    peak_memory,
+                    marker,
+                    record.args()
+                )
+            }
+            LogFormat::Json => serde_json::json!({
+                "time": time.to_string(),
+                "level": record.level().as_str(),
+                "elapsed_seconds": elapsed,
+                "peak_memory_kb": peak_memory,
+                "message": record.args().to_string(),
+            })
+            .to_string(),
+        }
+    }
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        // there is nowhere left to report a failure to write to STDERR
+        let _ = writeln!(std::io::stderr().lock(), "{}", self.format_record(record));
+    }
+
+    fn flush(&self) {
+        let _ = std::io::stderr().flush();
+    }
+}
+
+/// A named stage of a subcommand, such as reading the GFA.
+///
+/// When it is finished, the time it took is logged, along with the
+/// peak memory so far.
+pub struct Stage {
+    /// What is being done, e.g. "Reading GFA".
+    name: String,
+    /// When the stage started.
+    start: Instant,
+}
+
+impl Stage {
+    /// Start a stage.
+    pub fn start<S: Into<String>>(name: S) -> Self {
+        let name = name.into();
+        log::debug!("{}.", name);
+        Self {
+            name,
+            start: Instant::now(),
+        }
+    }
+
+    /// Finish a stage, logging how long it took.
+    pub fn finish(self) {
+        log::info!(
+            "{} took {:.2}s.",
+            self.name,
+            self.start.elapsed().as_secs_f64()
+        );
+    }
+}
+
+/// The peak resident memory of this process in kilobytes, where known (Linux only).
+pub fn peak_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|kb| kb.trim().trim_end_matches("kB").trim().parse().ok())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_log_format() {
+        assert_eq!(Logger::level(0, 0), LevelFilter::Info);
+        assert_eq!(Logger::level(1, 0), LevelFilter::Warn);
+        assert_eq!(Logger::level(3, 0), LevelFilter::Error);
+        assert_eq!(Logger::level(0, 2), LevelFilter::Trace);
+
+        let logger = Logger {
+            level: LevelFilter::Info,
+            format: LogFormat::Json,
+            start: Instant::now(),
+        };
+        let line = logger.format_record(
+            &Record::builder()
+                .args(format_args!("Read {} segments.", 3))
+                .level(Level::Warn)
+                .build(),
+        );
+        let json: serde_json::Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(json["level"], "WARN");
+        assert_eq!(json["message"], "Read 3 segments.");
+        assert!(json["elapsed_seconds"].is_f64());
+
+        let logger = Logger {
+            format: LogFormat::Text,
+            ..logger
+        };
+        let line = logger.format_record(
+            &Record::builder()
+                .args(format_args!("Read {} segments.", 3))
+                .level(Level::Info)
+                .build(),
+        );
+        assert!(line.ends_with("\t[+]\tRead 3 segments."));
+    }
+}
@@ -6,15 +6,477 @@ use std::path::PathBuf;
 
 use anyhow::Result;
 use clap::{crate_version, value_parser, Arg, ArgAction, Command};
-use gfatk::SSC;
+use gfatk::error::exit_code;
+use gfatk::logging::{LogFormat, Logger};
+use gfatk::{
+    bubbles, copy_number, dot, extract, extract_chloro, extract_mito, fasta, index, linear,
+    overlap, path, rename, stats, trim, validate, SSC,
+};
+use log::error;
 
-fn main() -> Result<()> {
+fn main() {
+    if let Err(e) = run() {
+        // errors go through the logger, unless it could not be set up
+        match log::max_level() {
+            log::LevelFilter::Off => eprintln!("Error: {:#}", e),
+            _ => error!("{:#}", e),
+        }
+        std::process::exit(exit_code(&e));
+    }
+}
+
+fn run() -> Result<()> {
     let matches = Command::new("gfatk")
         .version(crate_version!())
         .propagate_version(true)
         .arg_required_else_help(true)
         .author("Max Brown <mb39@sanger.ac.uk>")
         .about("Explore and linearise (plant organellar) GFA files.")
+        .arg(
+            Arg::new("quiet")
+                .short('q')
+                .long("quiet")
+                .global(true)
+                .action(ArgAction::Count)
+                .help("Only log warnings (-q) or errors (-qq) to STDERR."),
+        )
+        .arg(
+            Arg::new("verbose")
+                .short('v')
+                .long("verbose")
+                .global(true)
+                .action(ArgAction::Count)
+                .conflicts_with("quiet")
+                .help("Also log debugging (-v) or all (-vv) messages to STDERR."),
+        )
+        .arg(
+            Arg::new("log-format")
+                .long("log-format")
+                .global(true)
+                .default_value("text")
+                .value_parser(["text", "json"])
+                .help("Write log lines as tab separated text, or as JSON objects."),
+        )
+        .arg(
+            Arg::new("coverage-tags")
+                .long("coverage-tags")
+                .global(true)
+                .value_delimiter(',')
+                .default_value("ec,ll,dp,SC,RC,KC")
+                .value_parser(["ec", "ll", "dp", "SC", "RC", "KC"])
+                .help("Tags to read segment and link coverage from, in order of preference. RC and KC are divided by the segment length."),
+        )
+        .arg(
+            Arg::new("derive-edge-coverage")
+                .long("derive-edge-coverage")
+                .global(true)
+                .action(ArgAction::SetTrue)
+                .help("Give links without coverage the lower coverage of the two segments they join."),
+        )
+        .subcommand(
+            Command::new("overlap")
+                .about("Extract overlaps from a GFA.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("size")
+                        .short('s')
+                        .long("size")
+                        .default_value("1000")
+                        .value_parser(value_parser!(usize))
+                        .help("Region around overlap to extract."),
+                ),
+        )
+        .subcommand(
+            Command::new("extract")
+                .about("Extract subgraph from a GFA, given a segment name.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("sequence-ids")
+                        .short('s')
+                        .long("sequence-ids")
+                        .required(true)
+                        .num_args(1..)
+                        .value_delimiter(',')
+                        .value_parser(value_parser!(String))
+                        .help("Extract subgraph of which this sequence is part of. Multiple sequence ID's can be specified, comma separated."),
+                )
+                .arg(
+                    Arg::new("iterations")
+                        .short('i')
+                        .long("iterations")
+                        .default_value("3")
+                        .value_parser(value_parser!(i32))
+                        .help("Number of iterations to recursively search for connecting nodes."),
+                ),
+        )
+        .subcommand(
+            Command::new("linear")
+                .about("Force a linear representation of the graph.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("evaluate-subgraphs")
+                        .short('e')
+                        .long("evaluate-subgraphs")
+                        .action(ArgAction::SetTrue)
+                        .help("Linearise each subgraph in the GFA separately."),
+                )
+                .arg(
+                    Arg::new("include-node-coverage")
+                        .short('i')
+                        .long("include-node-coverage")
+                        .action(ArgAction::SetTrue)
+                        .help("Include node coverage in the path choice, so segments may appear as many times as their relative coverage."),
+                )
+                .arg(
+                    Arg::new("node-threshold")
+                        .short('n')
+                        .long("node-threshold")
+                        .default_value("200")
+                        .value_parser(value_parser!(usize))
+                        .help("Maximum number of nodes in a (sub)graph to attempt linearisation."),
+                )
+                .arg(
+                    Arg::new("circular")
+                        .short('c')
+                        .long("circular")
+                        .action(ArgAction::SetTrue)
+                        .help("Print every distinct circular walk through the graph (e.g. the master circle and sub-circles of a mitochondrion), instead of the single best path."),
+                )
+                .arg(
+                    Arg::new("max-isoforms")
+                        .long("max-isoforms")
+                        .default_value("100")
+                        .value_parser(value_parser!(usize))
+                        .help("Maximum number of circular walks to print with `--circular`."),
+                )
+                .arg(
+                    Arg::new("start-segment")
+                        .long("start-segment")
+                        .value_parser(value_parser!(String))
+                        .conflicts_with("start-motif")
+                        .requires("circular")
+                        .help("With `--circular`, start each isoform at this segment."),
+                )
+                .arg(
+                    Arg::new("start-motif")
+                        .long("start-motif")
+                        .value_parser(value_parser!(String))
+                        .requires("circular")
+                        .help("With `--circular`, start each isoform at the first occurrence of this sequence (e.g. a conserved gene start). If it is only on the reverse strand, the isoform is reverse complemented."),
+                ),
+        )
+        .subcommand(
+            Command::new("fasta")
+                .about("Extract a fasta file.\nAlmost as simple as: awk '/^S/{print \">\"$2\"\\n\"$3}'.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("segments")
+                        .short('s')
+                        .long("segments")
+                        .value_delimiter(',')
+                        .requires("GFA")
+                        .help("Only print these segments (comma separated names), read through the sequence index."),
+                ),
+        )
+        .subcommand(
+            Command::new("index")
+                .about("Index the segment sequences of a GFA, for random access.\nWrites <GFA>.gfai, with the name, length, and byte offset of each sequence.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .required(true)
+                        .help("Input GFA file (not compressed).")
+                ),
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Some stats about the input GFA.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("tabular")
+                        .short('t')
+                        .long("tabular")
+                        .action(ArgAction::SetTrue)
+                        .help("Print tabular (TSV) output."),
+                ),
+        )
+        .subcommand(
+            Command::new("extract-mito")
+                .about("Extract the mitochondria from a GFA.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("size-lower")
+                        .long("size-lower")
+                        .default_value("200000")
+                        .value_parser(value_parser!(usize))
+                        .help("Lower bound for the mitochondrial genome size."),
+                )
+                .arg(
+                    Arg::new("size-upper")
+                        .long("size-upper")
+                        .default_value("2000000")
+                        .value_parser(value_parser!(usize))
+                        .help("Upper bound for the mitochondrial genome size."),
+                )
+                .arg(
+                    Arg::new("gc-lower")
+                        .long("gc-lower")
+                        .default_value("0.42")
+                        .value_parser(value_parser!(f32))
+                        .help("Lower bound for the mitochondrial GC content."),
+                )
+                .arg(
+                    Arg::new("gc-upper")
+                        .long("gc-upper")
+                        .default_value("0.50")
+                        .value_parser(value_parser!(f32))
+                        .help("Upper bound for the mitochondrial GC content."),
+                ),
+        )
+        .subcommand(
+            Command::new("extract-chloro")
+                .about("Extract the plastid from a GFA.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("size-lower")
+                        .long("size-lower")
+                        .default_value("100000")
+                        .value_parser(value_parser!(usize))
+                        .help("Lower bound for the plastid genome size."),
+                )
+                .arg(
+                    Arg::new("size-upper")
+                        .long("size-upper")
+                        .default_value("200000")
+                        .value_parser(value_parser!(usize))
+                        .help("Upper bound for the plastid genome size."),
+                )
+                .arg(
+                    Arg::new("gc-lower")
+                        .long("gc-lower")
+                        .default_value("0.34")
+                        .value_parser(value_parser!(f32))
+                        .help("Lower bound for the plastid GC content."),
+                )
+                .arg(
+                    Arg::new("gc-upper")
+                        .long("gc-upper")
+                        .default_value("0.41")
+                        .value_parser(value_parser!(f32))
+                        .help("Upper bound for the plastid GC content."),
+                ),
+        )
+        .subcommand(
+            Command::new("dot")
+                .about("Return the dot representation of a GFA.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                ),
+        )
+        .subcommand(
+            Command::new("trim")
+                .about("Trim a GFA to remove nodes of degree < 4 (i.e. only has one neighbour).")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                ),
+        )
+        .subcommand(
+            Command::new("path")
+                .about("Supply an input path to evaluate a linear representation of.\nInput must be a text file of a single comma separated line with node ID's and orientations. E.g. 1+,2-,3+")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("path_cli")
+                        .value_parser(value_parser!(String))
+                        .help("The path, specified on the command line."),
+                )
+                .arg(
+                    Arg::new("path_file")
+                        .short('p')
+                        .long("path")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("The path, specified in a file."),
+                )
+                .arg(
+                    Arg::new("all_paths")
+                        .short('a')
+                        .long("all")
+                        .action(ArgAction::SetTrue)
+                        .help("Evaluate all the paths (P lines) in the GFA."),
+                )
+                .arg(
+                    Arg::new("between")
+                        .short('b')
+                        .long("between")
+                        .value_parser(value_parser!(String))
+                        .conflicts_with_all(["path_cli", "path_file", "all_paths"])
+                        .help("Evaluate every path between two oriented segments, e.g. 1+,3-, passing each segment at most once."),
+                )
+                .arg(
+                    Arg::new("max-paths")
+                        .long("max-paths")
+                        .default_value("100")
+                        .value_parser(value_parser!(usize))
+                        .requires("between")
+                        .help("Maximum number of paths to print with `--between`."),
+                )
+                .arg(
+                    Arg::new("circular")
+                        .short('c')
+                        .long("circular")
+                        .action(ArgAction::SetTrue)
+                        .help("Treat a path which closes on itself as circular, and trim the overlap at the junction."),
+                )
+                .arg(
+                    Arg::new("start-segment")
+                        .long("start-segment")
+                        .value_parser(value_parser!(String))
+                        .conflicts_with("start-motif")
+                        .requires("circular")
+                        .help("With `--circular`, start the sequence at this segment."),
+                )
+                .arg(
+                    Arg::new("start-motif")
+                        .long("start-motif")
+                        .value_parser(value_parser!(String))
+                        .requires("circular")
+                        .help("With `--circular`, start the sequence at the first occurrence of this motif (e.g. a conserved gene start). If it is only on the reverse strand, the sequence is reverse complemented."),
+                ),
+        )
+        .subcommand(
+            Command::new("rename")
+                .about("Rename the segment ID's of a GFA.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file (or, with --reverse, the output of a gfatk subcommand).")
+                )
+                .arg(
+                    Arg::new("mapping")
+                        .short('m')
+                        .long("mapping")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Write the old and new segment names to this TSV file."),
+                )
+                .arg(
+                    Arg::new("reverse")
+                        .short('r')
+                        .long("reverse")
+                        .action(ArgAction::SetTrue)
+                        .requires("mapping")
+                        .help("Restore the original segment names from the --mapping file."),
+                ),
+        )
+        .subcommand(
+            Command::new("validate")
+                .about("Check a GFA for problems, such as dangling or duplicate links, reporting them with line numbers.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .default_value("tsv")
+                        .value_parser(["tsv", "json"])
+                        .help("Report the problems in this format."),
+                ),
+        )
+        .subcommand(
+            Command::new("bubbles")
+                .about("Find the bubbles and superbubbles in a GFA, with the alleles through each one.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .default_value("tsv")
+                        .value_parser(["tsv", "bed"])
+                        .help("Report each bubble (source, sink, segments and alleles) in this format."),
+                )
+                .arg(
+                    Arg::new("max_size")
+                        .short('m')
+                        .long("max-size")
+                        .default_value("1000")
+                        .value_parser(value_parser!(usize))
+                        .help("Maximum number of (oriented) segments inside a bubble."),
+                )
+                .arg(
+                    Arg::new("max_alleles")
+                        .short('a')
+                        .long("max-alleles")
+                        .default_value("100")
+                        .value_parser(value_parser!(usize))
+                        .help("Maximum number of alleles (paths through a bubble) to print for each bubble."),
+                ),
+        )
+        .subcommand(
+            Command::new("copy-number")
+                .about("Estimate the copy number of each segment from its coverage.\nPrints the GFA with a CN:i tag on each segment.")
+                .arg(
+                    Arg::new("GFA")
+                        .value_parser(value_parser!(PathBuf))
+                        .help("Input GFA file.")
+                )
+                .arg(
+                    Arg::new("min-length")
+                        .short('l')
+                        .long("min-length")
+                        .default_value("1000")
+                        .value_parser(value_parser!(usize))
+                        .help("Minimum length of the segments the single copy coverage of each subgraph is fitted to (all segments are used if none are this long)."),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .default_value("gfa")
+                        .value_parser(["gfa", "tsv"])
+                        .help("Print the GFA with CN:i tags, or the coverage, baseline, copy number and confidence of each segment."),
+                ),
+        )
         .subcommand(
             Command::new("SSC")
                 .about("Extract Strongly Connected Components from a GFA.")
@@ -30,16 +492,106 @@ fn main() -> Result<()> {
                         .default_value("5")
                         .value_parser(value_parser!(usize))
                         .help("min SSC size to consider."),
+                )
+                .arg(
+                    Arg::new("format")
+                        .short('f')
+                        .long("format")
+                        .value_parser(["tsv", "json", "bed"])
+                        .help("Report each component (segments, counts, length, entries and exits) in this format."),
+                )
+                .arg(
+                    Arg::new("condense")
+                        .short('c')
+                        .long("condense")
+                        .value_parser(["gfa", "dot"])
+                        .conflicts_with("format")
+                        .help("Print the condensed graph, with each component collapsed to a single segment, in this format."),
+                )
+                .arg(
+                    Arg::new("outdir")
+                        .short('o')
+                        .long("outdir")
+                        .value_parser(value_parser!(PathBuf))
+                        .conflicts_with_all(["format", "condense"])
+                        .help("Write each component to its own GFA in this directory, with a manifest.tsv."),
+                )
+                .arg(
+                    Arg::new("context")
+                        .long("context")
+                        .default_value("0")
+                        .value_parser(value_parser!(i32))
+                        .requires("outdir")
+                        .help("Number of links of flanking segments to add around each component written to --outdir."),
+                )
+                .arg(
+                    Arg::new("threads")
+                        .short('t')
+                        .long("threads")
+                        .default_value("1")
+                        .value_parser(value_parser!(usize))
+                        .help("Number of threads to search the weakly connected components with (0 uses all cores)."),
                 ),
         ).get_matches();
-                
+
+    let level = Logger::level(matches.get_count("quiet"), matches.get_count("verbose"));
+    let format = match matches.get_one::<String>("log-format").map(|f| f.as_str()) {
+        Some("json") => LogFormat::Json,
+        _ => LogFormat::Text,
+    };
+    Logger::init(level, format)?;
 
     match matches.subcommand() {
+        Some(("overlap", matches)) => {
+            overlap::overlap(matches)?;
+        }
+        Some(("extract", matches)) => {
+            extract::extract(matches)?;
+        }
+        Some(("linear", matches)) => {
+            linear::linear(matches)?;
+        }
+        Some(("fasta", matches)) => {
+            fasta::fasta(matches)?;
+        }
+        Some(("index", matches)) => {
+            index::index(matches)?;
+        }
+        Some(("stats", matches)) => {
+            stats::stats(matches, stats::GenomeType::None)?;
+        }
+        Some(("extract-mito", matches)) => {
+            extract_mito::extract_mito(matches, stats::GenomeType::Mitochondria)?;
+        }
+        Some(("extract-chloro", matches)) => {
+            extract_chloro::extract_chloro(matches, stats::GenomeType::Chloroplast)?;
+        }
+        Some(("dot", matches)) => {
+            dot::dot(matches)?;
+        }
+        Some(("trim", matches)) => {
+            trim::trim(matches)?;
+        }
+        Some(("path", matches)) => {
+            path::path(matches)?;
+        }
+        Some(("rename", matches)) => {
+            rename::rename_gfa(matches)?;
+        }
+        Some(("validate", matches)) => {
+            validate::validate(matches)?;
+        }
+        Some(("bubbles", matches)) => {
+            bubbles::bubbles(matches)?;
+        }
+        Some(("copy-number", matches)) => {
+            copy_number::copy_number(matches)?;
+        }
         Some(("SSC", matches)) => {
             SSC::get_strong_terminal_nodes(matches)?;
         }
         _ => {
-            eprintln!("Subcommand invalid, run with '--help' for subcommand options. Exiting.");
+            error!("Subcommand invalid, run with '--help' for subcommand options. Exiting.");
             std::process::exit(1);
         }
     }
@@ -1,7 +1,8 @@
 use std::path::PathBuf;
 
+use crate::error::GfatkError;
 use crate::gfa::gfa::GFAtk;
-use crate::load::{load_gfa, load_gfa_stdin};
+use crate::load::{load_gfatk, load_gfatk_stdin};
 use crate::utils;
 use anyhow::{bail, Result};
 
@@ -17,21 +18,16 @@ pub fn overlap(matches: &clap::ArgMatches) -> Result<()> {
 
     let gfa: GFAtk = match gfa_file {
         Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
-                }
-                None => bail!("Could not read file."),
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => GFAtk(load_gfa_stdin(std::io::stdin().lock())?),
-            false => bail!("No input from STDIN. Run `gfatk path -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk path -h` for help.".into()
+            )),
         },
     };
 
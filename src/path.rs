@@ -1,9 +1,15 @@
-use crate::gfa::gfa::GFAtk;
-use crate::load::{load_gfa, load_gfa_stdin};
+use crate::gfa::cigar::Cigar;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::{into_digraph, GFAtk};
+use crate::gfa::graph::{all_paths, flip_orientation, oriented_index, OrientedSegment};
+use crate::gfa::names::SegmentNames;
+use crate::load::{load_gfatk, load_gfatk_stdin};
 use crate::utils;
 
-use anyhow::{bail, ensure, Context, Result};
+use crate::error::GfatkError;
+use anyhow::{bail, Result};
 use gfa::gfa::Orientation;
+use log::warn;
 use std::collections::HashMap;
 use std::fs;
 use std::io::{BufRead, BufReader};
@@ -28,6 +34,10 @@ pub enum CLIOpt {
 /// For example:
 /// ```bash
 /// gfatk path ./input.gfa "12+, 11+, 2-, 2+"
+/// # a circular genome, starting at segment 11
+/// gfatk path --circular --start-segment 11 ./input.gfa "12+, 11+, 2-, 2+"
+/// # every path from 12+ to 2+
+/// gfatk path --between 12+,2+ ./input.gfa
 /// ```
 pub fn path(matches: &clap::ArgMatches) -> Result<()> {
     // read in path and parse gfa
@@ -35,43 +45,79 @@ pub fn path(matches: &clap::ArgMatches) -> Result<()> {
     let path_cli = matches.get_one::<String>("path_cli");
     let path_file = matches.get_one::<PathBuf>("path_file");
     let all_p_lines = matches.get_flag("all_paths");
+    let between = matches.get_one::<String>("between");
+    let max_paths = *matches
+        .get_one::<usize>("max-paths")
+        .expect("defaulted by clap");
+    let is_circular = matches.get_flag("circular");
 
     let gfa: GFAtk = match gfa_file {
         Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
-                }
-                None => bail!("Could not read file."),
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => GFAtk(load_gfa_stdin(std::io::stdin().lock())?),
-            false => bail!("No input from STDIN. Run `gfatk path -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk path -h` for help.".into()
+            )),
         },
     };
 
+    let circular = match is_circular {
+        true => Some(Circular::from_matches(matches, &gfa.1, true)?),
+        false => None,
+    };
+
     if all_p_lines {
         let paths = gfa.get_path_lines()?;
         for (id, path) in paths {
             let (parsed_path, link_map) = parse_path(&path, CLIOpt::String, &gfa)?;
-            gfa.from_path_cli(parsed_path, link_map, "path_all", Some(id))?;
+            gfa.from_path_cli(
+                parsed_path,
+                link_map,
+                "path_all",
+                Some(id),
+                circular.as_ref(),
+            )?;
+        }
+    } else if let Some(between) = between {
+        let (ends, link_map) = parse_path(between, CLIOpt::String, &gfa)?;
+        let ends: Vec<OrientedSegment> = ends.segments().collect();
+        let [from, to] = ends[..] else {
+            bail!(GfatkError::Input(
+                "`--between` takes two oriented segments, e.g. 1+,3-.".into()
+            ))
+        };
+        let (graph_indices, gfa_graph) = into_digraph(&gfa.0, &CoverageTags::default())?;
+        let node = |s: OrientedSegment| oriented_index(graph_indices[&s.seg_id], s.orientation);
+
+        let paths = all_paths(&gfa_graph, node(from), node(to), None, max_paths);
+        if paths.is_empty() {
+            warn!(
+                "There is no path from {} to {}.",
+                gfa.1.oriented(from),
+                gfa.1.oriented(to)
+            );
+        }
+        for path in paths {
+            gfa.from_path_cli(path, link_map.clone(), "path", None, circular.as_ref())?;
         }
     } else {
         // we need some path specified
         if path_cli.is_none() && path_file.is_none() {
-            bail!(
-            "Please specify either a path as a positional argument string, or as a file `--path`."
-        )
+            bail!(GfatkError::Input(
+                "Please specify either a path as a positional argument string, or as a file `--path`."
+                    .into()
+            ))
         }
         // but not both!
         if path_cli.is_some() && path_file.is_some() {
-            bail!("Specify either <path>, or `--path`, not both.")
+            bail!(GfatkError::Input(
+                "Specify either <path>, or `--path`, not both.".into()
+            ))
         }
 
         let (path, link_map) = match path_cli {
@@ -85,7 +131,7 @@ pub fn path(matches: &clap::ArgMatches) -> Result<()> {
             },
         }?;
 
-        gfa.from_path_cli(path, link_map, "path", None)?;
+        gfa.from_path_cli(path, link_map, "path", None, circular.as_ref())?;
     }
 
     Ok(())
@@ -97,7 +143,7 @@ pub fn parse_path(
     path: &str,
     is_cli: CLIOpt,
     gfa: &GFAtk,
-) -> Result<(GFAPath, HashMap<String, usize>)> {
+) -> Result<(GFAPath, HashMap<String, Cigar>)> {
     match is_cli {
         CLIOpt::String => parse_path_string(path, gfa),
         CLIOpt::File => {
@@ -113,11 +159,127 @@ pub fn parse_path(
     }
 }
 
-/// A GFA path element. Of the form `<segment ID><+/->`
+/// How to print a path which closes on itself, e.g. a circular genome.
+///
+/// The overlap of the link from the last segment back to the first is
+/// trimmed from the start of the sequence, so the junction is not
+/// duplicated. The circle can then be rotated to start at a segment, or at
+/// a sequence motif (e.g. a conserved gene start).
+#[derive(Debug, Clone, Default)]
+pub struct Circular {
+    /// Start the sequence at the first occurrence of this segment ID.
+    pub start_segment: Option<usize>,
+    /// Start the sequence at the first occurrence of this motif (upper case).
+    pub start_motif: Option<Vec<u8>>,
+    /// Whether it is an error for the start segment or motif to be missing
+    /// from the path. Otherwise the path is printed without rotation.
+    pub required: bool,
+}
+
+impl Circular {
+    /// Read the rotation options of `path` and `linear` from the CLI.
+    pub fn from_matches(
+        matches: &clap::ArgMatches,
+        names: &SegmentNames,
+        required: bool,
+    ) -> Result<Self> {
+        let start_segment = match matches.get_one::<String>("start-segment") {
+            Some(name) => Some(names.try_id(name)?),
+            None => None,
+        };
+        let start_motif = match matches.get_one::<String>("start-motif") {
+            Some(motif) if motif.is_empty() => {
+                bail!(GfatkError::Input("The start motif is empty.".into()))
+            }
+            Some(motif) => Some(motif.to_ascii_uppercase().into_bytes()),
+            None => None,
+        };
+
+        Ok(Self {
+            start_segment,
+            start_motif,
+            required,
+        })
+    }
+
+    /// Rotate the sequence of a circular path, given where each element of
+    /// the path starts in it.
+    ///
+    /// Returns `true` if the motif was only found on the reverse strand, in
+    /// which case the reverse complement of the sequence is returned.
+    pub fn rotate(
+        &self,
+        sequence: &mut Vec<u8>,
+        path: &GFAPath,
+        starts: &[usize],
+        names: &SegmentNames,
+    ) -> Result<bool> {
+        if let Some(segment) = self.start_segment {
+            match path.inner.iter().position(|el| el.segment_id == segment) {
+                Some(position) => sequence.rotate_left(starts[position]),
+                None => {
+                    return self.missing(format!(
+                        "The start segment {} is not in the path",
+                        names.name(segment)
+                    ))
+                }
+            }
+        }
+
+        if let Some(motif) = &self.start_motif {
+            if let Some(position) = find_circular(sequence, motif) {
+                sequence.rotate_left(position);
+            } else {
+                let revcomp = utils::reverse_complement(sequence);
+                match find_circular(&revcomp, motif) {
+                    Some(position) => {
+                        *sequence = revcomp;
+                        sequence.rotate_left(position);
+                        return Ok(true);
+                    }
+                    None => {
+                        return self.missing(format!(
+                            "The start motif {} is not in the path, on either strand",
+                            String::from_utf8_lossy(motif)
+                        ))
+                    }
+                }
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Error if the start of the circle is required, otherwise warn.
+    fn missing(&self, message: String) -> Result<bool> {
+        if self.required {
+            bail!(GfatkError::Input(format!("{}.", message)))
+        }
+        warn!("{}, so it is not rotated.", message);
+        Ok(false)
+    }
+}
+
+/// The first position of `motif` in a circular `sequence`, ignoring case.
+/// The motif may span the end and the start of the sequence.
+fn find_circular(sequence: &[u8], motif: &[u8]) -> Option<usize> {
+    if motif.is_empty() || motif.len() > sequence.len() {
+        return None;
+    }
+    let wrapped: Vec<u8> = sequence
+        .iter()
+        .chain(&sequence[..motif.len() - 1])
+        .map(|b| b.to_ascii_uppercase())
+        .collect();
+
+    wrapped.windows(motif.len()).position(|w| w == motif)
+}
+
+/// A GFA path element. Of the form `<segment name><+/->`
 #[derive(Debug, Clone, Copy)]
 pub struct GFAPathElement {
     /// The ID of the GFA path element.
-    /// It must match that of the segments in the GFA.
+    /// It must match that of the segments in the GFA (see [`SegmentNames`]).
     pub segment_id: usize,
     /// The orientation of the segment.
     pub orientation: Orientation,
@@ -140,33 +302,82 @@ impl GFAPath {
     fn push(&mut self, other: GFAPathElement) {
         self.inner.push(other);
     }
+    /// Make a [`GFAPath`] from the oriented segments of a walk through a
+    /// [`GFAdigraph`](crate::gfa::graph::GFAdigraph).
+    pub fn from_segments(segments: &[OrientedSegment]) -> Self {
+        let mut path = Self::new();
+        for (index, segment) in segments.iter().enumerate() {
+            path.push(GFAPathElement {
+                segment_id: segment.seg_id,
+                orientation: segment.orientation,
+                index,
+            });
+        }
+        path
+    }
+    /// The oriented segments of the path.
+    pub fn segments(&self) -> impl Iterator<Item = OrientedSegment> + '_ {
+        self.inner.iter().map(|el| OrientedSegment {
+            seg_id: el.segment_id,
+            orientation: el.orientation,
+        })
+    }
+    /// The number of elements in the path.
+    pub fn len(&self) -> usize {
+        self.inner.len()
+    }
+    /// Whether the path has no elements.
+    pub fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
     /// Convert to a string for inclusion in the fasta header.
-    pub fn to_fasta_header(&self) -> String {
+    pub fn to_fasta_header(&self, names: &SegmentNames) -> String {
         let mut output = String::new();
 
         for el in &self.inner {
-            output += &format!("{}{},", el.segment_id, el.orientation);
+            output += &format!("{}{},", names.name(el.segment_id), el.orientation);
         }
         output.pop();
         output
     }
 }
 
-/// Parses a path string to a [`GFAPath`] object.
-fn parse_path_string(path_string: &str, gfa: &GFAtk) -> Result<(GFAPath, HashMap<String, usize>)> {
-    let gfa = &gfa.0;
+/// A map of each link of a GFA (and its reverse complement) to its overlap.
+///
+/// Keys are of the form `<from ID><+/->|<to ID><+/->`, as looked up by
+/// [`GFAtk::from_path_cli`].
+pub fn link_overlaps(gfa: &GFAtk) -> Result<HashMap<String, Cigar>> {
     // make a map of the links
     let mut link_map = HashMap::new();
-    for link in &gfa.links {
+    for link in &gfa.0.links {
         let path_pair = format!(
             "{}{}|{}{}",
             link.from_segment, link.from_orient, link.to_segment, link.to_orient
         );
-        let cigar = utils::parse_cigar(&link.overlap)?;
+        // the same link, traversed on the other strand
+        let reverse_pair = format!(
+            "{}{}|{}{}",
+            link.to_segment,
+            flip_orientation(link.to_orient),
+            link.from_segment,
+            flip_orientation(link.from_orient)
+        );
+        let cigar = Cigar::parse(&link.overlap)?;
 
+        link_map.entry(reverse_pair).or_insert_with(|| cigar.flip());
         link_map.insert(path_pair, cigar);
     }
 
+    Ok(link_map)
+}
+
+/// Parses a path string to a [`GFAPath`] object.
+///
+/// Also returns a map of each link (and its reverse complement) to its overlap.
+fn parse_path_string(path_string: &str, gfa: &GFAtk) -> Result<(GFAPath, HashMap<String, Cigar>)> {
+    let names = &gfa.1;
+    let link_map = link_overlaps(gfa)?;
+
     // path_string consists of e.g.:
     // 1+, 2-, 3+, 4-, ...
     // split
@@ -180,26 +391,21 @@ fn parse_path_string(path_string: &str, gfa: &GFAtk) -> Result<(GFAPath, HashMap
 
     for (index, token) in split_path.into_iter().enumerate() {
         let mut token_string = token.to_owned();
-        let orientation = token_string
-            .pop()
-            .context("Each path element should contain a character.")?;
-
-        ensure!(
-            orientation == '-' || orientation == '+',
-            "The last char in the split_path token was {}, not \'+\' or \'-\'. Check path is specified correctly.",
-            orientation
-        );
+        let orientation = token_string.pop().ok_or_else(|| {
+            GfatkError::Input("Each path element should contain a character.".into())
+        })?;
 
         let o_enum = match orientation {
             '+' => Orientation::Forward,
             '-' => Orientation::Backward,
-            _ => bail!("Orientation can only be the chars \'+\' or \'-\'."),
+            _ => bail!(GfatkError::Input(format!(
+                "The last char in the path element {} was {}, not \'+\' or \'-\'. Check path is specified correctly.",
+                token, orientation
+            ))),
         };
 
         gfa_path.push(GFAPathElement {
-            segment_id: token_string
-                .parse::<usize>()
-                .context("Could not parse the token string as a `usize`.")?,
+            segment_id: names.try_id(&token_string)?,
             orientation: o_enum,
             index,
         });
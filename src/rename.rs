@@ -1,41 +1,395 @@
-use std::path::PathBuf;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
 
-use crate::load::{load_gfa, load_gfa_stdin};
+use crate::error::GfatkError;
+use crate::load::{decompressed_reader, load_gfatk, load_gfatk_stdin};
 use crate::utils;
-use anyhow::{bail, Result};
-use gfa::{gfa::name_conversion::NameMap, gfa::GFA, optfields::OptionalFields};
+use anyhow::{bail, Context, Result};
 
+/// Report fields (`gfatk SSC`, `gfatk stats`) which hold comma separated,
+/// possibly oriented, segment names.
+const SEGMENT_FIELDS: [&str; 4] = ["segments", "entries", "exits", "context_segments"];
+
+/// The header line of a mapping file written by `gfatk rename`.
+const MAPPING_HEADER: &str = "#old_name\tnew_name";
+
+/// A mapping from the new segment names given by `gfatk rename` back to the
+/// original names.
+pub struct NameMapping(pub HashMap<String, String>);
+
+impl NameMapping {
+    /// Read a mapping file of `<old_name>\t<new_name>` lines.
+    ///
+    /// Lines starting with `#` are skipped.
+    pub fn from_file(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Could not read mapping file {:?}", path))?;
+
+        let mut mapping = HashMap::new();
+        let mut old_names = HashSet::new();
+        for (line_no, line) in contents.lines().enumerate() {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (old, new) = match line.split_once('\t') {
+                Some((old, new)) if !old.is_empty() && !new.is_empty() && !new.contains('\t') => {
+                    (old, new)
+                }
+                _ => bail!(
+                    "Line {} of {:?} is not of the form <old_name>\\t<new_name>.",
+                    line_no + 1,
+                    path
+                ),
+            };
+            if !old_names.insert(old.to_string()) {
+                bail!("Segment {} is renamed more than once in {:?}.", old, path);
+            }
+            if mapping.insert(new.to_string(), old.to_string()).is_some() {
+                bail!("More than one segment is renamed to {} in {:?}.", new, path);
+            }
+        }
+
+        Ok(Self(mapping))
+    }
+
+    /// Restore a segment name. Anything else is returned unchanged.
+    pub fn restore(&self, item: &str) -> String {
+        self.0
+            .get(item)
+            .cloned()
+            .unwrap_or_else(|| item.to_string())
+    }
+
+    /// As [`NameMapping::restore`], but the segment may also have a trailing
+    /// orientation (`+`/`-`).
+    pub fn restore_oriented(&self, item: &str) -> String {
+        if let Some(old) = self.0.get(item) {
+            return old.clone();
+        }
+        match item.strip_suffix(['+', '-']) {
+            Some(name) if self.0.contains_key(name) => {
+                format!("{}{}", self.0[name], &item[name.len()..])
+            }
+            _ => item.to_string(),
+        }
+    }
+
+    /// Restore each item of a comma separated list of (oriented) segments.
+    fn restore_list(&self, list: &str) -> String {
+        list.split(',')
+            .map(|item| self.restore_oriented(item))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+
+    /// Restore each segment of a walk, e.g. `>1<2>3`.
+    fn restore_walk(&self, walk: &str) -> String {
+        walk.matches(['>', '<'])
+            .zip(walk.split(['>', '<']).skip(1))
+            .map(|(orient, name)| format!("{}{}", orient, self.restore(name)))
+            .collect()
+    }
+
+    /// Restore the segment names in a GFA line.
+    fn restore_gfa_line(&self, line: &str) -> String {
+        let mut fields: Vec<String> = line.split('\t').map(|f| f.to_string()).collect();
+        let restore_at = |fields: &mut Vec<String>, i: usize| {
+            if let Some(field) = fields.get(i) {
+                fields[i] = self.restore(field);
+            }
+        };
+        match fields[0].as_str() {
+            "S" => restore_at(&mut fields, 1),
+            "L" | "C" => {
+                restore_at(&mut fields, 1);
+                restore_at(&mut fields, 3);
+            }
+            "P" => {
+                if let Some(steps) = fields.get(2) {
+                    fields[2] = self.restore_list(steps);
+                }
+            }
+            "W" => {
+                if let Some(walk) = fields.get(6) {
+                    fields[6] = self.restore_walk(walk);
+                }
+            }
+            "J" => {
+                restore_at(&mut fields, 1);
+                restore_at(&mut fields, 3);
+            }
+            _ => (),
+        }
+        fields.join("\t")
+    }
+
+    /// Restore the segment names in a FASTA header (without the `>`).
+    ///
+    /// The whole sequence ID is restored if it is a segment name. Otherwise,
+    /// only oriented segments (`12+`, `12(+)`) are restored, so that other
+    /// numbers in the header (e.g. `coverage=2`) are left alone.
+    fn restore_fasta_header(&self, header: &str) -> String {
+        let (id, rest) = header
+            .split_once(char::is_whitespace)
+            .map(|(id, _)| (id, &header[id.len()..]))
+            .unwrap_or((header, ""));
+        if let Some(old) = self.0.get(id) {
+            return format!("{}{}", old, rest);
+        }
+
+        let is_delimiter = |c: char| ",;:=>()| \t".contains(c);
+        let mut restored = String::new();
+        let mut token_start = 0;
+        for (i, c) in header
+            .char_indices()
+            .chain(std::iter::once((header.len(), ' ')))
+        {
+            if !is_delimiter(c) {
+                continue;
+            }
+            let token = &header[token_start..i];
+            let following = &header[i..];
+            let is_oriented = match token.strip_suffix(['+', '-']) {
+                Some(name) => self.0.contains_key(name),
+                None => following.starts_with("(+)") || following.starts_with("(-)"),
+            };
+            if is_oriented {
+                restored += &self.restore_oriented(token);
+            } else {
+                restored += token;
+            }
+            if i < header.len() {
+                restored.push(c);
+            }
+            token_start = i + c.len_utf8();
+        }
+        restored
+    }
+
+    /// Restore the segment names in a JSON report, in any of the
+    /// [`SEGMENT_FIELDS`].
+    fn restore_json(&self, value: &mut serde_json::Value) {
+        use serde_json::Value;
+        match value {
+            Value::Array(values) => values.iter_mut().for_each(|v| self.restore_json(v)),
+            Value::Object(map) => {
+                for (key, v) in map.iter_mut() {
+                    if !SEGMENT_FIELDS.contains(&key.as_str()) {
+                        self.restore_json(v);
+                        continue;
+                    }
+                    match v {
+                        Value::String(s) => *s = self.restore_list(s),
+                        Value::Array(items) => {
+                            for item in items {
+                                if let Value::String(s) = item {
+                                    *s = self.restore_oriented(s);
+                                }
+                            }
+                        }
+                        _ => (),
+                    }
+                }
+            }
+            _ => (),
+        }
+    }
+
+    /// Restore the original segment names in the output of `gfatk`.
+    ///
+    /// The input may be a GFA, a FASTA, a JSON report, a TSV report with a
+    /// header (where the [`SEGMENT_FIELDS`] columns are restored), or
+    /// anything else tab separated with segments in the first column (e.g. a
+    /// BED file, or a path such as `1+,2-,3+`).
+    pub fn restore_text(&self, input: &str) -> Result<String> {
+        let first_line = input.lines().find(|l| !l.trim().is_empty()).unwrap_or("");
+
+        let mut output = String::with_capacity(input.len());
+        let mut push_line = |line: String| {
+            output += &line;
+            output.push('\n');
+        };
+
+        if ["H\t", "S\t", "L\t", "C\t", "P\t", "W\t", "J\t"]
+            .iter()
+            .any(|p| first_line.starts_with(p))
+        {
+            input
+                .lines()
+                .for_each(|line| push_line(self.restore_gfa_line(line)));
+        } else if first_line.starts_with('>') {
+            for line in input.lines() {
+                match line.strip_prefix('>') {
+                    Some(header) => push_line(format!(">{}", self.restore_fasta_header(header))),
+                    None => push_line(line.to_string()),
+                }
+            }
+        } else if first_line.starts_with('[') || first_line.starts_with('{') {
+            let mut json: serde_json::Value =
+                serde_json::from_str(input).context("Could not parse JSON input")?;
+            self.restore_json(&mut json);
+            push_line(
+                serde_json::to_string_pretty(&json).context("Could not serialise JSON output")?,
+            );
+        } else {
+            let headers: Vec<&str> = first_line.split('\t').collect();
+            let mut columns: Vec<usize> = headers
+                .iter()
+                .enumerate()
+                .filter(|(_, h)| SEGMENT_FIELDS.contains(h))
+                .map(|(i, _)| i)
+                .collect();
+            let has_header = !columns.is_empty();
+            if !has_header {
+                columns.push(0);
+            }
+
+            for (line_no, line) in input.lines().enumerate() {
+                if (has_header && line_no == 0) || line.starts_with('#') {
+                    push_line(line.to_string());
+                    continue;
+                }
+                let mut fields: Vec<String> = line.split('\t').map(|f| f.to_string()).collect();
+                for column in &columns {
+                    if let Some(field) = fields.get(*column) {
+                        fields[*column] = self.restore_list(field);
+                    }
+                }
+                push_line(fields.join("\t"));
+            }
+        }
+
+        Ok(output)
+    }
+}
+
+/// Rename the segments of a GFA to consecutive integers, so it can be used by the
+/// rest of `gfatk`.
+///
+/// With `--mapping`, the old and new names are written to a TSV. With
+/// `--reverse`, this mapping is used to restore the original segment names in
+/// a GFA, or in the output of other `gfatk` subcommands.
+///
+/// For example:
+/// ```bash
+/// gfatk rename in.gfa -m mapping.tsv > out.gfa
+/// gfatk SSC out.gfa -f tsv | gfatk rename -r -m mapping.tsv
+/// ```
 pub fn rename_gfa(matches: &clap::ArgMatches) -> Result<()> {
     let gfa_file = matches.get_one::<PathBuf>("GFA");
+    let mapping_file = matches.get_one::<PathBuf>("mapping");
 
-    let gfa: GFA<Vec<u8>, OptionalFields> = match gfa_file {
-        Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        load_gfa(f)?
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
+    if matches.get_flag("reverse") {
+        let mapping_file = mapping_file.expect("required by clap");
+        let mapping = NameMapping::from_file(mapping_file)?;
+
+        let mut input = String::new();
+        match gfa_file {
+            Some(f) => {
+                let file = File::open(f).with_context(|| format!("Could not open {:?}", f))?;
+                decompressed_reader(file)?
+                    .read_to_string(&mut input)
+                    .with_context(|| format!("Could not read {:?}", f))?;
+            }
+            None => match utils::is_stdin() {
+                true => {
+                    decompressed_reader(std::io::stdin().lock())?
+                        .read_to_string(&mut input)
+                        .context("Could not read STDIN")?;
                 }
-                None => bail!("Could not read file."),
+                false => bail!(GfatkError::Input(
+                    "No input from STDIN. Run `gfatk rename -h` for help.".into()
+                )),
+            },
+        }
+
+        print!("{}", mapping.restore_text(&input)?);
+        return Ok(());
+    }
+
+    let gfa = match gfa_file {
+        Some(f) => {
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => load_gfa_stdin(std::io::stdin().lock())?,
-            false => bail!("No input from STDIN. Run `gfatk path -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk rename -h` for help.".into()
+            )),
         },
     };
+    let names = &gfa.1;
 
-    let name_map = NameMap::build_from_gfa(&gfa);
-
-    if let Some(new_gfa) = name_map.gfa_bytestring_to_usize(&gfa, false) {
-        let gfa_string = crate::gfa::gfa_string(&new_gfa);
-        println!("{}", gfa_string);
-    } else {
-        bail!("Could not convert segment ID's to usize.")
+    if let Some(mapping_file) = mapping_file {
+        let mut writer = BufWriter::new(
+            File::create(mapping_file)
+                .with_context(|| format!("Could not create {:?}", mapping_file))?,
+        );
+        writeln!(writer, "{}", MAPPING_HEADER)?;
+        for id in 0..names.len() {
+            writeln!(writer, "{}\t{}", names.name(id), id)?;
+        }
+        writer.flush()?;
     }
 
+    // the segment ID's are already consecutive integers
+    let gfa_string = crate::gfa::gfa_string(&gfa.0) + &gfa.2.to_string();
+    println!("{}", gfa_string);
+
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    fn make_mapping() -> NameMapping {
+        NameMapping(HashMap::from([
+            ("0".to_string(), "utg000001l".to_string()),
+            ("1".to_string(), "utg000002l".to_string()),
+            ("2".to_string(), "utg000003l".to_string()),
+        ]))
+    }
+
+    #[test]
+    fn test_restore_text() {
+        let mapping = make_mapping();
+
+        let gfa = "S\t0\tACGT\nL\t0\t+\t1\t-\t2M\nP\tp1\t0+,1-\t2M\n";
+        assert_eq!(
+            mapping.restore_text(gfa).unwrap(),
+            "S\tutg000001l\tACGT\nL\tutg000001l\t+\tutg000002l\t-\t2M\nP\tp1\tutg000001l+,utg000002l-\t2M\n"
+        );
+
+        let gfa = "W\tHG002\t1\tchr1\t*\t*\t>0<1>2\nJ\t0\t+\t2\t-\t*\n";
+        assert_eq!(
+            mapping.restore_text(gfa).unwrap(),
+            "W\tHG002\t1\tchr1\t*\t*\t>utg000001l<utg000002l>utg000003l\nJ\tutg000001l\t+\tutg000003l\t-\t*\n"
+        );
+
+        // other numbers in the header are not segments
+        let fasta = ">gfatk_linear:path=0+,1-:coverage=2\nACGT\n>0(+)->2(-): extend = 1\nACGT\n";
+        assert_eq!(
+            mapping.restore_text(fasta).unwrap(),
+            ">gfatk_linear:path=utg000001l+,utg000002l-:coverage=2\nACGT\n>utg000001l(+)->utg000003l(-): extend = 1\nACGT\n"
+        );
+
+        let tsv = "scc_index\tsegments\tnode_count\tentries\n1\t1,2\t2\t0+\n";
+        assert_eq!(
+            mapping.restore_text(tsv).unwrap(),
+            "scc_index\tsegments\tnode_count\tentries\n1\tutg000002l,utg000003l\t2\tutg000001l+\n"
+        );
+
+        let bed = "2\t0\t7\tscc_0\n";
+        assert_eq!(
+            mapping.restore_text(bed).unwrap(),
+            "utg000003l\t0\t7\tscc_0\n"
+        );
+    }
+}
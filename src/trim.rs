@@ -1,9 +1,9 @@
 use std::path::PathBuf;
 
-use crate::gfa::gfa::GFAtk;
-use crate::gfa::gfa_string;
-use crate::gfa::graph::segments_subgraph;
-use crate::load::{load_gfa, load_gfa_stdin};
+use crate::error::GfatkError;
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::gfa::{into_digraph, GFAtk};
+use crate::load::{load_gfatk, load_gfatk_stdin};
 use crate::utils;
 use anyhow::{bail, Result};
 
@@ -19,26 +19,26 @@ pub fn trim(matches: &clap::ArgMatches) -> Result<()> {
 
     let gfa: GFAtk = match gfa_file {
         Some(f) => {
-            let ext = f.extension();
-            match ext {
-                Some(e) => {
-                    if e == "gfa" {
-                        GFAtk(load_gfa(f)?)
-                    } else {
-                        bail!("Input is not a GFA.")
-                    }
-                }
-                None => bail!("Could not read file."),
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
             }
+            load_gfatk(f)?
         }
         None => match utils::is_stdin() {
-            true => GFAtk(load_gfa_stdin(std::io::stdin().lock())?),
-            false => bail!("No input from STDIN. Run `gfatk extract -h` for help."),
+            true => load_gfatk_stdin(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk extract -h` for help.".into()
+            )),
         },
     };
 
-    let (graph_indices, gfa_graph) = gfa.into_digraph()?;
+    let coverage_tags = CoverageTags::from_matches(matches)?;
+    let (_, gfa_graph) = into_digraph(&gfa.0, &coverage_tags)?;
+    gfa_graph.ensure_edge_coverage(&gfa.1, &coverage_tags)?;
 
+    let trimmed = gfa_graph.trim(&gfa.1);
+
+    println!("{}", gfa.subgraph(trimmed).to_gfa_string());
 
     Ok(())
 }
@@ -1,9 +1,13 @@
+use crate::gfa::coverage::CoverageTags;
+use crate::gfa::optfields::OptionalTags;
 use anyhow::{bail, Context, Result};
 use atty::Stream;
+use bstr::ByteSlice;
 use gfa::optfields::{OptField, OptFieldVal::*};
 use petgraph::graph::NodeIndex;
 use std::collections::HashMap;
 use std::fmt;
+use std::path::Path;
 
 /// Format a sequence length (`usize`) to kilobases.
 pub fn format_usize_to_kb(num: usize) -> String {
@@ -16,81 +20,92 @@ pub fn is_stdin() -> bool {
     !atty::is(Stream::Stdin)
 }
 
-/// Get the coverage associated with an edge (`ec` tag in the GFA).
-pub fn get_edge_coverage(options: &[OptField]) -> Result<i64> {
-    if let Some(op) = options.iter().next() {
-        match op.tag {
-            // ec
-            [101, 99] => match op.value {
-                Int(i) => return Ok(i),
-                _ => bail!("Could not find integer ec:i:<i64> tag."),
-            },
-            _ => bail!("Could not find ec (edge coverage) tag."),
-        };
-    }
-    bail!("Edge coverage not found.")
+/// Check if a path looks like a GFA, which may be gzip/bgzip compressed.
+pub fn is_gfa_path(path: &Path) -> bool {
+    let name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_ascii_lowercase())
+        .unwrap_or_default();
+    [".gfa", ".gfa.gz", ".gfa.bgz"]
+        .iter()
+        .any(|ext| name.ends_with(ext))
 }
 
-/// Format a GFA option field into a string.
-pub fn get_option_string(options: Vec<OptField>) -> Result<String> {
-    let mut tag_val = String::new();
-    for op in options {
-        let tag = std::str::from_utf8(&op.tag)
-            .with_context(|| format!("Malformed UTF8: {:?}", op.tag))?;
-        let value = match op.value {
-            Float(f) => format!(":f:{:.3}", f),
-            A(a) => format!(":A:{}", a),
-            Int(i) => format!(":i:{}", i),
-            Z(z) => format!(
-                ":Z:{}",
-                std::str::from_utf8(&z).with_context(|| format!("Malformed UTF8: {:?}", z))?
-            ),
-            // J(j) => ???,
-            // a hexadecimal array
-            H(h) => format!(":H:{}", h.iter().map(|x| x.to_string()).collect::<String>()),
-            // B is a general array
-            // is it capital B?
-            BInt(bi) => format!(
-                ":B:{}",
-                bi.iter().map(|x| x.to_string()).collect::<String>()
-            ),
-            BFloat(bf) => format!(
-                ":B:{}",
-                bf.iter().map(|x| format!("{:.3}", x)).collect::<String>()
-            ),
-            _ => "".to_string(),
-        };
-        tag_val += &format!("{}{}\t", tag, value);
+/// Get the coverage associated with an edge, from the first of the
+/// coverage tags it has.
+pub fn get_edge_coverage(options: &[OptField], coverage_tags: &CoverageTags) -> Result<i64> {
+    match coverage_tags.link_coverage(options) {
+        Some(coverage) => Ok(coverage),
+        None => bail!(coverage_tags.missing("edge coverage not found.".into())),
     }
-    // should always end in \t ^
-    let tag_val_op_un = tag_val
-        .strip_suffix('\t')
-        .context("Could not strip a tab from the suffix of the tag.")?;
-    Ok(tag_val_op_un.to_string())
 }
 
-/// Parse a CIGAR string slice into an overlap length.
-pub fn parse_cigar(cigar: &[u8]) -> Result<usize> {
-    // check it ends with an M
-    if !cigar.ends_with(&[77]) {
-        bail!(
-            "CIGAR string did not end with M: {}",
-            std::str::from_utf8(cigar).with_context(|| format!("Malformed UTF8: {:?}", cigar))?
-        );
-    }
-    let stripped = cigar.strip_suffix(&[77]);
-    match stripped {
-        Some(s) => {
-            let string_rep =
-                std::str::from_utf8(s).with_context(|| format!("Malformed UTF8: {:?}", s))?;
-            Ok(string_rep
-                .parse::<usize>()
-                .with_context(|| format!("{} could not be parsed to <usize>", string_rep))?)
-        }
-        None => bail!("Could not strip suffix (M) of the CIGAR string."),
+/// Format a GFA optional field (tag) into a string, as `<TAG>:<TYPE>:<VALUE>`.
+///
+/// The gfa crate keeps only the values of a `B` array, not its subtype, so
+/// an integer array is written with `int_array_subtype` (the subtype it was
+/// read with, see [`OptionalTags`]), or if there is none, the narrowest
+/// subtype which holds all its values.
+pub fn get_option_field_string(option: &OptField, int_array_subtype: Option<u8>) -> String {
+    let tag = option.tag.as_bstr();
+    match &option.value {
+        A(a) => format!("{}:A:{}", tag, *a as char),
+        Int(i) => format!("{}:i:{}", tag, i),
+        Float(f) => format!("{}:f:{}", tag, f),
+        Z(z) => format!("{}:Z:{}", tag, z.as_bstr()),
+        J(j) => format!("{}:J:{}", tag, j.as_bstr()),
+        // a hexadecimal array, which the gfa crate stores one digit at a time
+        H(h) => format!(
+            "{}:H:{}",
+            tag,
+            h.iter().map(|x| format!("{:X}", x)).collect::<String>()
+        ),
+        BInt(bi) => format!(
+            "{}:B:{}{}",
+            tag,
+            int_array_subtype.map_or_else(|| narrowest_int_array_subtype(bi), char::from),
+            bi.iter().map(|x| format!(",{}", x)).collect::<String>()
+        ),
+        BFloat(bf) => format!(
+            "{}:B:f{}",
+            tag,
+            bf.iter().map(|x| format!(",{}", x)).collect::<String>()
+        ),
     }
 }
 
+/// The narrowest `B` array subtype which holds all of these integers.
+///
+/// Arrays of integers outside of the 32 bit range are not in the spec,
+/// and are written as `i`.
+fn narrowest_int_array_subtype(array: &[i64]) -> char {
+    let (min, max) = (
+        array.iter().copied().min().unwrap_or(0),
+        array.iter().copied().max().unwrap_or(0),
+    );
+    [
+        ('c', i8::MIN as i64, i8::MAX as i64),
+        ('C', u8::MIN as i64, u8::MAX as i64),
+        ('s', i16::MIN as i64, i16::MAX as i64),
+        ('S', u16::MIN as i64, u16::MAX as i64),
+        ('i', i32::MIN as i64, i32::MAX as i64),
+        ('I', u32::MIN as i64, u32::MAX as i64),
+    ]
+    .iter()
+    .find(|(_, lower, upper)| min >= *lower && max <= *upper)
+    .map(|(subtype, _, _)| *subtype)
+    .unwrap_or('i')
+}
+
+/// Format GFA optional fields into a tab separated string.
+pub fn get_option_string(options: &OptionalTags) -> String {
+    options
+        .iter()
+        .map(|option| get_option_field_string(option, options.int_array_subtype(&option.tag)))
+        .collect::<Vec<String>>()
+        .join("\t")
+}
+
 /// Reverse complement a string slice.
 pub fn reverse_complement(dna: &[u8]) -> Vec<u8> {
     let dna_vec = dna.to_vec();
@@ -164,8 +179,7 @@ pub struct GFAGraphPair {
 ///
 /// This should 100% have been a map-like structure...
 
-
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Default)]
 pub struct GFAGraphLookups(pub Vec<GFAGraphPair>);
 
 impl GFAGraphLookups {
@@ -234,6 +248,7 @@ impl fmt::Display for GFAGraphLookups {
 mod tests {
 
     use super::*;
+    use gfa::optfields::OptFields;
 
     #[test]
     fn test_node_seg_id_indexes() {
@@ -254,23 +269,80 @@ mod tests {
     }
 
     #[test]
-    fn test_gc_content() {
-        let dna_bytes = vec![b'A', b'G', b'G', b'T', b'T', b'C'];
+    fn test_is_gfa_path() {
+        assert!(is_gfa_path(Path::new("in.gfa")));
+        assert!(is_gfa_path(Path::new("dir/in.GFA.gz")));
+        assert!(is_gfa_path(Path::new("in.gfa.bgz")));
+        assert!(!is_gfa_path(Path::new("in.fa.gz")));
+        assert!(!is_gfa_path(Path::new("gfa")));
+    }
 
-        let gc = gc_content(&dna_bytes);
+    #[test]
+    fn test_option_string() {
+        let options: OptionalTags = vec![
+            OptField::new(b"ch", A(b'x')),
+            OptField::new(b"hx", H(vec![1, 10, 14, 3])),
+            OptField::new(b"bc", BInt(vec![-1, 2, 3])),
+            OptField::new(b"bs", BInt(vec![300, 65535])),
+            OptField::new(b"bl", BInt(vec![-1, 5_000_000_000])),
+            OptField::new(b"bf", BFloat(vec![0.5, 1.0, -2.25])),
+        ]
+        .into();
+
+        // without a subtype read, integer arrays take the narrowest
+        assert_eq!(
+            get_option_string(&options),
+            "ch:A:x\thx:H:1AE3\tbc:B:c,-1,2,3\tbs:B:S,300,65535\tbl:B:i,-1,5000000000\tbf:B:f,0.5,1,-2.25"
+        );
 
-        assert_eq!(gc, 0.5);
+        let options = OptionalTags::parse(["bi:B:i,1,2", "bs:B:s,-1,3", "bc:B:c,1"]);
+
+        assert_eq!(
+            get_option_string(&options),
+            "bi:B:i,1,2\tbs:B:s,-1,3\tbc:B:c,1"
+        );
+    }
+
+    #[test]
+    fn test_option_string_round_trip() {
+        // every tag in the test GFA's is read back in as it was written out
+        for entry in std::fs::read_dir("tests").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension() != Some("gfa".as_ref()) {
+                continue;
+            }
+            let gfa = std::fs::read(&path).unwrap();
+            for line in gfa.lines() {
+                for field in line.split_str("\t").skip(1) {
+                    if field.len() < 5 || field[2] != b':' || field[4] != b':' {
+                        continue;
+                    }
+                    let options = OptionalTags::parse([field]);
+                    let option = options[0].clone();
+                    let written = get_option_string(&options);
+
+                    assert_eq!(
+                        OptField::parse(written.as_bytes()),
+                        Some(option),
+                        "{} in {}",
+                        field.as_bstr(),
+                        path.display()
+                    );
+                    // floats may lose trailing zeros, e.g. 30.0 to 30
+                    if path.ends_with("test_tags.gfa") {
+                        assert_eq!(written.as_bytes(), field);
+                    }
+                }
+            }
+        }
     }
 
     #[test]
-    fn test_cigar_parse() {
-        let cigar_ok = "120M".as_bytes();
-        let cigar_err = "30M10D20M5I10M".as_bytes();
+    fn test_gc_content() {
+        let dna_bytes = vec![b'A', b'G', b'G', b'T', b'T', b'C'];
 
-        let parsed_cigar = parse_cigar(cigar_ok).is_ok();
-        let parsed_cigar2 = parse_cigar(cigar_err).is_err();
+        let gc = gc_content(&dna_bytes);
 
-        assert!(parsed_cigar);
-        assert!(parsed_cigar2);
+        assert_eq!(gc, 0.5);
     }
 }
@@ -0,0 +1,584 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+use std::fs::File;
+use std::io::{BufRead, Read};
+use std::path::PathBuf;
+
+use crate::error::GfatkError;
+use crate::gfa::cigar::Cigar;
+use crate::gfa::graph::OrientedSegment;
+use crate::gfa::names::SegmentNames;
+use crate::gfa::walk::{Jump, Walk};
+use crate::load::decompressed_reader;
+use crate::utils;
+use anyhow::{bail, Context, Result};
+use bstr::ByteSlice;
+use gfa::gfa::Orientation;
+use gfa::optfields::{OptFieldVal, OptFields, OptionalFields};
+use log::{info, warn};
+use serde::{Serialize, Serializer};
+
+/// A kind of problem found by `gfatk validate`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum ProblemKind {
+    /// A line which could not be parsed.
+    MalformedLine,
+    /// A segment name with more than one `S` line.
+    DuplicateSegment,
+    /// A segment without a sequence (`*`).
+    MissingSequence,
+    /// A segment whose `LN` tag is not the length of its sequence.
+    LengthMismatch,
+    /// A link or jump to a segment with no `S` line.
+    DanglingLink,
+    /// A link given more than once.
+    ///
+    /// A link given again as its reverse complement is not a duplicate, as
+    /// some assemblers (e.g. MBG) write both.
+    DuplicateLink,
+    /// A CIGAR string which could not be parsed.
+    InvalidCigar,
+    /// A CIGAR string which is not a plain overlap (`<int>M`). These are
+    /// used by `gfatk`, but other GFA tools may reject them.
+    NonMatchCigar,
+    /// An overlap longer than one of the segments it joins.
+    OverlapTooLong,
+    /// A path or walk through a segment with no `S` line.
+    DanglingPath,
+    /// A path or walk stepping between two segments which are not linked.
+    PathMissingLink,
+}
+
+impl ProblemKind {
+    /// Whether this kind of problem breaks other subcommands (an error), or
+    /// only limits them (a warning).
+    pub fn is_error(&self) -> bool {
+        !matches!(
+            self,
+            ProblemKind::MissingSequence | ProblemKind::DuplicateLink | ProblemKind::NonMatchCigar
+        )
+    }
+
+    /// The snake case name of the problem, as in the output.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProblemKind::MalformedLine => "malformed_line",
+            ProblemKind::DuplicateSegment => "duplicate_segment",
+            ProblemKind::MissingSequence => "missing_sequence",
+            ProblemKind::LengthMismatch => "length_mismatch",
+            ProblemKind::DanglingLink => "dangling_link",
+            ProblemKind::DuplicateLink => "duplicate_link",
+            ProblemKind::InvalidCigar => "invalid_cigar",
+            ProblemKind::NonMatchCigar => "non_match_cigar",
+            ProblemKind::OverlapTooLong => "overlap_too_long",
+            ProblemKind::DanglingPath => "dangling_path",
+            ProblemKind::PathMissingLink => "path_missing_link",
+        }
+    }
+}
+
+impl fmt::Display for ProblemKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl Serialize for ProblemKind {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+/// A problem on a line of a GFA.
+#[derive(Clone, Debug, Serialize)]
+pub struct Problem {
+    /// The 1-based line number.
+    pub line: usize,
+    /// What kind of problem it is.
+    pub kind: ProblemKind,
+    /// A description of the problem.
+    pub message: String,
+}
+
+/// A link or jump, as read from its line.
+struct LinkLine {
+    line: usize,
+    from: OrientedSegment,
+    to: OrientedSegment,
+    /// The CIGAR string, or `None` for a jump.
+    cigar: Option<Vec<u8>>,
+}
+
+/// A path or walk, as read from its line.
+struct PathLine {
+    line: usize,
+    /// e.g. `Path p1` or `Walk HG002#1#chr1`.
+    label: String,
+    steps: Vec<OrientedSegment>,
+}
+
+/// The problems found in a GFA, in line order.
+pub struct Validation(pub Vec<Problem>);
+
+impl Validation {
+    /// Check a (possibly compressed) GFA, streamed from a reader.
+    pub fn from_reader<R: Read>(reader: R) -> Result<Self> {
+        let mut reader = decompressed_reader(reader)?;
+
+        let mut problems = Vec::new();
+        let mut names = SegmentNames::default();
+        // the line and length (if known) of each segment, by ID
+        let mut segments: Vec<Option<(usize, Option<usize>)>> = Vec::new();
+        let mut links = Vec::new();
+        let mut paths = Vec::new();
+
+        let mut line = Vec::new();
+        let mut line_no = 0;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_no += 1;
+            let line = line.trim_end_with(|c| c == '\n' || c == '\r');
+            let mut segment_id = |name: &[u8]| {
+                let id = names.intern(name);
+                if id == segments.len() {
+                    segments.push(None);
+                }
+                id
+            };
+
+            let parsed = match line.first() {
+                Some(b'S') => Self::read_segment(line, line_no, &mut segment_id).map(|s| {
+                    let (id, length, mut found) = s;
+                    match segments[id] {
+                        Some((first, _)) => problems.push(Problem {
+                            line: line_no,
+                            kind: ProblemKind::DuplicateSegment,
+                            message: format!(
+                                "Segment {} is already defined on line {}.",
+                                names.name(id),
+                                first
+                            ),
+                        }),
+                        None => segments[id] = Some((line_no, length)),
+                    }
+                    problems.append(&mut found);
+                }),
+                Some(b'L') => {
+                    Self::read_link(line, line_no, &mut segment_id).map(|l| links.push(l))
+                }
+                Some(b'J') => Jump::<Vec<u8>>::parse_line(line)
+                    .map_err(|e| anyhow::anyhow!("not a valid jump: {}", e))
+                    .map(|jump| {
+                        let jump = jump.map_segments(|name| segment_id(name));
+                        links.push(LinkLine {
+                            line: line_no,
+                            from: oriented(jump.from_segment, jump.from_orient),
+                            to: oriented(jump.to_segment, jump.to_orient),
+                            cigar: None,
+                        })
+                    }),
+                Some(b'P') => Self::read_path(line, &mut segment_id).map(|(label, steps)| {
+                    paths.push(PathLine {
+                        line: line_no,
+                        label,
+                        steps,
+                    })
+                }),
+                Some(b'W') => Walk::<Vec<u8>>::parse_line(line)
+                    .map_err(|e| anyhow::anyhow!("not a valid walk: {}", e))
+                    .map(|walk| {
+                        let walk = walk.map_segments(|name| segment_id(name));
+                        paths.push(PathLine {
+                            line: line_no,
+                            label: format!("Walk {}", walk.haplotype()),
+                            steps: walk.steps.iter().map(|(s, o)| oriented(*s, *o)).collect(),
+                        })
+                    }),
+                _ => Ok(()),
+            };
+            if let Err(e) = parsed {
+                problems.push(Problem {
+                    line: line_no,
+                    kind: ProblemKind::MalformedLine,
+                    message: format!("{:#}", e),
+                });
+            }
+        }
+
+        Self::check_links(&links, &segments, &names, &mut problems);
+        Self::check_paths(&paths, &links, &segments, &names, &mut problems);
+
+        problems.sort_by_key(|p| (p.line, p.kind));
+        Ok(Self(problems))
+    }
+
+    /// Read a segment line, returning its ID, length (if known), and any problems.
+    fn read_segment(
+        line: &[u8],
+        line_no: usize,
+        segment_id: &mut impl FnMut(&[u8]) -> usize,
+    ) -> Result<(usize, Option<usize>, Vec<Problem>)> {
+        let fields: Vec<&[u8]> = line.split_str("\t").collect();
+        if fields.len() < 3 {
+            bail!("a segment line needs a name and a sequence.");
+        }
+        let id = segment_id(fields[1]);
+        let optional = OptionalFields::parse(fields[3..].iter());
+        let ln = match optional.get_field(b"LN").map(|ln| &ln.value) {
+            Some(OptFieldVal::Int(ln)) => Some(*ln as usize),
+            _ => None,
+        };
+
+        let mut problems = Vec::new();
+        let name = fields[1].as_bstr();
+        let length = match fields[2] {
+            b"*" => {
+                problems.push(Problem {
+                    line: line_no,
+                    kind: ProblemKind::MissingSequence,
+                    message: format!("Segment {} has no sequence.", name),
+                });
+                ln
+            }
+            sequence => {
+                if let Some(ln) = ln.filter(|ln| *ln != sequence.len()) {
+                    problems.push(Problem {
+                        line: line_no,
+                        kind: ProblemKind::LengthMismatch,
+                        message: format!(
+                            "Segment {} has LN:i:{}, but its sequence is {} long.",
+                            name,
+                            ln,
+                            sequence.len()
+                        ),
+                    });
+                }
+                Some(sequence.len())
+            }
+        };
+        Ok((id, length, problems))
+    }
+
+    /// Read a link line.
+    fn read_link(
+        line: &[u8],
+        line_no: usize,
+        segment_id: &mut impl FnMut(&[u8]) -> usize,
+    ) -> Result<LinkLine> {
+        let fields: Vec<&[u8]> = line.split_str("\t").collect();
+        if fields.len() < 6 {
+            bail!("a link line needs two oriented segments and an overlap.");
+        }
+        Ok(LinkLine {
+            line: line_no,
+            from: oriented(segment_id(fields[1]), parse_orientation(fields[2])?),
+            to: oriented(segment_id(fields[3]), parse_orientation(fields[4])?),
+            cigar: Some(fields[5].to_vec()),
+        })
+    }
+
+    /// Read a path line, returning its label and steps.
+    fn read_path(
+        line: &[u8],
+        segment_id: &mut impl FnMut(&[u8]) -> usize,
+    ) -> Result<(String, Vec<OrientedSegment>)> {
+        let fields: Vec<&[u8]> = line.split_str("\t").collect();
+        if fields.len() < 3 {
+            bail!("a path line needs a name and segments.");
+        }
+        let steps = fields[2]
+            .split_str(",")
+            .map(|step| {
+                let (name, orient) = step.split_at(step.len().saturating_sub(1));
+                Ok(oriented(segment_id(name), parse_orientation(orient)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok((format!("Path {}", fields[1].as_bstr()), steps))
+    }
+
+    /// Check links and jumps for undefined segments, duplicates, and overlaps.
+    fn check_links(
+        links: &[LinkLine],
+        segments: &[Option<(usize, Option<usize>)>],
+        names: &SegmentNames,
+        problems: &mut Vec<Problem>,
+    ) {
+        let mut seen: HashMap<(OrientedSegment, OrientedSegment, bool), usize> = HashMap::new();
+
+        for link in links {
+            let what = match link.cigar {
+                Some(_) => "Link",
+                None => "Jump",
+            };
+            let description = format!(
+                "{} {} -> {}",
+                what,
+                names.oriented(link.from),
+                names.oriented(link.to)
+            );
+
+            let undefined: Vec<String> = [link.from.seg_id, link.to.seg_id]
+                .iter()
+                .filter(|id| segments[**id].is_none())
+                .map(|id| names.name(*id).to_string())
+                .collect();
+            if let Some(name) = undefined.first() {
+                problems.push(Problem {
+                    line: link.line,
+                    kind: ProblemKind::DanglingLink,
+                    message: format!(
+                        "{} uses segment {}, which has no S line.",
+                        description, name
+                    ),
+                });
+                continue;
+            }
+
+            let key = (link.from, link.to, link.cigar.is_none());
+            if let Some(first) = seen.get(&key) {
+                problems.push(Problem {
+                    line: link.line,
+                    kind: ProblemKind::DuplicateLink,
+                    message: format!("{} is already given on line {}.", description, first),
+                });
+            } else {
+                seen.insert(key, link.line);
+            }
+
+            let cigar = match &link.cigar {
+                Some(cigar) => cigar,
+                None => continue,
+            };
+            match Cigar::parse(cigar) {
+                Ok(overlap) => {
+                    if !overlap.is_plain_overlap() {
+                        problems.push(Problem {
+                            line: link.line,
+                            kind: ProblemKind::NonMatchCigar,
+                            message: format!(
+                                "{} has CIGAR {}, which is not a plain overlap (<int>M).",
+                                description, overlap
+                            ),
+                        });
+                    }
+                    for (id, overlap) in [
+                        (link.from.seg_id, overlap.from_length()),
+                        (link.to.seg_id, overlap.to_length()),
+                    ] {
+                        let length = segments[id].and_then(|(_, length)| length);
+                        if let Some(length) = length.filter(|length| overlap > *length) {
+                            problems.push(Problem {
+                                line: link.line,
+                                kind: ProblemKind::OverlapTooLong,
+                                message: format!(
+                                    "{} overlaps segment {} by {}, but it is {} long.",
+                                    description,
+                                    names.name(id),
+                                    overlap,
+                                    length
+                                ),
+                            });
+                        }
+                    }
+                }
+                Err(e) => problems.push(Problem {
+                    line: link.line,
+                    kind: ProblemKind::InvalidCigar,
+                    message: format!("{}: {}", description, e),
+                }),
+            }
+        }
+    }
+
+    /// Check that paths and walks use defined segments, and follow links or jumps.
+    fn check_paths(
+        paths: &[PathLine],
+        links: &[LinkLine],
+        segments: &[Option<(usize, Option<usize>)>],
+        names: &SegmentNames,
+        problems: &mut Vec<Problem>,
+    ) {
+        let mut adjacent: HashSet<(OrientedSegment, OrientedSegment)> = HashSet::new();
+        for link in links {
+            adjacent.insert((link.from, link.to));
+            adjacent.insert((link.to.flip(), link.from.flip()));
+        }
+
+        for path in paths {
+            if let Some(step) = path.steps.iter().find(|s| segments[s.seg_id].is_none()) {
+                problems.push(Problem {
+                    line: path.line,
+                    kind: ProblemKind::DanglingPath,
+                    message: format!(
+                        "{} uses segment {}, which has no S line.",
+                        path.label,
+                        names.name(step.seg_id)
+                    ),
+                });
+                continue;
+            }
+            for pair in path.steps.windows(2) {
+                if !adjacent.contains(&(pair[0], pair[1])) {
+                    problems.push(Problem {
+                        line: path.line,
+                        kind: ProblemKind::PathMissingLink,
+                        message: format!(
+                            "{} steps from {} to {}, but they are not linked.",
+                            path.label,
+                            names.oriented(pair[0]),
+                            names.oriented(pair[1])
+                        ),
+                    });
+                }
+            }
+        }
+    }
+
+    /// The number of problems which are errors (see [`ProblemKind::is_error`]).
+    pub fn error_count(&self) -> usize {
+        self.0.iter().filter(|p| p.kind.is_error()).count()
+    }
+
+    /// The number of problems of each kind.
+    pub fn summary(&self) -> BTreeMap<ProblemKind, usize> {
+        let mut summary = BTreeMap::new();
+        for problem in &self.0 {
+            *summary.entry(problem.kind).or_insert(0) += 1;
+        }
+        summary
+    }
+
+    /// Print the problems as a TSV.
+    pub fn print_tabular(&self) {
+        println!("line\tseverity\tproblem\tmessage");
+        for Problem {
+            line,
+            kind,
+            message,
+        } in &self.0
+        {
+            let severity = if kind.is_error() { "error" } else { "warning" };
+            println!("{}\t{}\t{}\t{}", line, severity, kind, message);
+        }
+    }
+
+    /// Print the problems as JSON.
+    pub fn print_json(&self) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.0)
+            .context("Could not serialise the problems to JSON")?;
+        println!("{}", json);
+        Ok(())
+    }
+}
+
+/// An oriented segment, from its ID and orientation.
+fn oriented(seg_id: usize, orientation: Orientation) -> OrientedSegment {
+    OrientedSegment {
+        seg_id,
+        orientation,
+    }
+}
+
+/// Parse a `+`/`-` orientation.
+fn parse_orientation(field: &[u8]) -> Result<Orientation> {
+    Orientation::from_bytes_plus_minus(field)
+        .with_context(|| format!("{} is not an orientation.", field.as_bstr()))
+}
+
+/// Check a GFA for problems which would break, or limit, the other subcommands.
+///
+/// Each problem is printed with its line number, and a summary is logged.
+/// Fails if any of the problems are errors rather than warnings.
+///
+/// For example:
+/// ```bash
+/// gfatk validate in.gfa
+/// gfatk validate in.gfa -f json
+/// ```
+pub fn validate(matches: &clap::ArgMatches) -> Result<()> {
+    let gfa_file = matches.get_one::<PathBuf>("GFA");
+
+    let validation = match gfa_file {
+        Some(f) => {
+            if !utils::is_gfa_path(f) {
+                bail!(GfatkError::Input("Input is not a GFA.".into()))
+            }
+            let file = File::open(f).with_context(|| format!("Could not open {:?}", f))?;
+            Validation::from_reader(file)?
+        }
+        None => match utils::is_stdin() {
+            true => Validation::from_reader(std::io::stdin().lock())?,
+            false => bail!(GfatkError::Input(
+                "No input from STDIN. Run `gfatk validate -h` for help.".into()
+            )),
+        },
+    };
+
+    match matches.get_one::<String>("format").map(|f| f.as_str()) {
+        Some("json") => validation.print_json()?,
+        _ => validation.print_tabular(),
+    }
+
+    for (kind, count) in validation.summary() {
+        match kind.is_error() {
+            true => warn!("{} x {} (error)", count, kind),
+            false => warn!("{} x {} (warning)", count, kind),
+        }
+    }
+
+    let errors = validation.error_count();
+    let warnings = validation.0.len() - errors;
+    if errors > 0 {
+        bail!(GfatkError::Invalid { errors, warnings });
+    }
+    info!("The GFA is valid, with {} warnings.", warnings);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+
+    use super::*;
+
+    #[test]
+    fn test_validate() {
+        let gfa = b"H\tVN:Z:1.0
+S\t1\tACGT\tLN:i:5
+S\t2\t*\tLN:i:3
+S\t1\tACGT
+L\t1\t+\t2\t+\t4M
+L\t1\t+\t2\t+\t1M
+L\t1\t+\t3\t-\t0M
+L\t2\t+\t1\t+\t2M1Q
+P\tp1\t1+,2+,1-\t*
+W\tHG002\t1\tchr1\t*\t*\t>1>2>1
+L\t1\t?\t2\t+\t0M
+L\t2\t+\t2\t+\t1M1I1M
+";
+        let validation = Validation::from_reader(&gfa[..]).unwrap();
+        let found: Vec<(usize, ProblemKind)> =
+            validation.0.iter().map(|p| (p.line, p.kind)).collect();
+
+        assert_eq!(
+            found,
+            vec![
+                (2, ProblemKind::LengthMismatch),
+                (3, ProblemKind::MissingSequence),
+                (4, ProblemKind::DuplicateSegment),
+                (5, ProblemKind::OverlapTooLong),
+                (6, ProblemKind::DuplicateLink),
+                (7, ProblemKind::DanglingLink),
+                (8, ProblemKind::InvalidCigar),
+                (9, ProblemKind::PathMissingLink),
+                (11, ProblemKind::MalformedLine),
+                (12, ProblemKind::NonMatchCigar),
+            ]
+        );
+        assert_eq!(validation.error_count(), 7);
+        assert_eq!(validation.summary()[&ProblemKind::DuplicateLink], 1);
+    }
+}
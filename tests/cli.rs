@@ -59,6 +59,33 @@ fn test_gfa_linear_single_segment() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+// the segments and links left out of the path are counted in the header
+// a link and its reverse complement count once
+//
+// S	5	ATATGC	ll:f:5.0
+// L	5	+	5	+	0M	ec:i:1
+// ...
+
+#[test]
+fn test_gfa_linear_unused() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("linear").arg("./tests/test_scc.gfa");
+    cmd.assert().success().stdout(predicate::str::contains(
+        ">gfatk_linear:path=1+,2+,3+,4+:coverage=4:unused_segments=1/5:unused_links=2/5\n",
+    ));
+
+    // the repeat is passed twice, so the link back to it is used
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("linear").arg("./tests/test_scc.gfa").arg("-i");
+    cmd.assert().success().stdout(predicate::str::contains(
+        ">gfatk_linear:path=1+,2+,3+,2+,3+,4+:coverage=8:unused_segments=1/5:unused_links=1/5\n",
+    ));
+
+    Ok(())
+}
+
 // test `gfatk overlap`
 
 // same test GFA as `gfatk linear`
@@ -315,6 +342,51 @@ fn test_gfa_edge_coverage_failure_stats() -> Result<(), Box<dyn std::error::Erro
     Ok(())
 }
 
+// coverage can be read from tags in any position, from
+// other tags, and derived for links from the segments.
+//
+// H	VN:Z:1.0
+// S	11	ACCTT	LN:i:5	KC:i:150
+// S	12	TCAAGG	LN:i:6	KC:i:360
+// S	13	CTTGATT	LN:i:7	KC:i:210
+// L	11	+	12	-	4M	RC:i:3	ec:i:1
+// ...
+
+#[test]
+fn test_gfa_coverage_tags() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("linear").arg("./tests/test_coverage_tags.gfa");
+    cmd.assert().success().stdout(predicate::str::contains(
+        ">gfatk_linear:path=11+,12-,13+:coverage=2:unused_segments=0/3:unused_links=1/3\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("linear")
+        .arg("./tests/test_coverage_tags.gfa")
+        .arg("--coverage-tags")
+        .arg("RC,KC");
+    cmd.assert().success().stdout(predicate::str::contains(
+        ">gfatk_linear:path=11+,12-,13+:coverage=6:unused_segments=0/3:unused_links=1/3\n",
+    ));
+
+    // KC is divided by the segment length, as ll:f in test_linear.gfa
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("stats").arg("./tests/test_coverage_tags.gfa");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "Average coverage of total segments:\t40\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("linear")
+        .arg("./tests/test_no_ec.gfa")
+        .arg("--derive-edge-coverage");
+    cmd.assert().success().stdout(predicate::str::contains(
+        ">gfatk_linear:path=11+,12-,13+:coverage=60:unused_segments=0/3:unused_links=1/3\n",
+    ));
+
+    Ok(())
+}
+
 // test segment coverage tag presence
 // only relevant for:
 // `gfatk linear -i <in.gfa>`
@@ -349,6 +421,58 @@ AATCAAGGT
     Ok(())
 }
 
+// test the optional fields of each type are written
+// back out as they were read in.
+
+#[test]
+fn test_gfa_tags_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("extract")
+        .arg("./tests/test_tags.gfa")
+        .arg("-s")
+        .arg("1");
+
+    cmd.assert()
+        .success()
+        .stdout(std::fs::read_to_string("./tests/test_tags.gfa")?);
+
+    Ok(())
+}
+
+// test `gfatk path` and `gfatk overlap`
+//
+// The link has an indel in its overlap, so it
+// consumes 3 bases of segment 1, and 4 of segment 2.
+//
+// H	VN:Z:1.0
+// S	1	AACCGG	ll:f:30.0
+// S	2	CGTTT	ll:f:30.0
+// L	1	+	2	+	2M1I1M	ec:i:1
+
+#[test]
+fn test_gfa_complex_cigar() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("path").arg("./tests/test_cigar.gfa").arg("1+,2+");
+    cmd.assert().success().stdout(">1+,2+\nAACCGGT\n");
+
+    // on the other strand, segment 1 is trimmed instead
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("path").arg("./tests/test_cigar.gfa").arg("2-,1-");
+    cmd.assert().success().stdout(">2-,1-\nAAACGGTT\n");
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("overlap")
+        .arg("./tests/test_cigar.gfa")
+        .arg("-s")
+        .arg("1");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(">1(+)->2(+): extend = 1\nCCGGT\n"));
+
+    Ok(())
+}
+
 // test `gfatk path`
 //
 // As this GFA is circular, a legal path could loop forever.
@@ -387,6 +511,71 @@ AGCGTAACAGCGTAACAGCGTA
     Ok(())
 }
 
+// with --circular, the 2M overlap of the link 2+ -> 1+ closing the
+// circle is trimmed from the start, and the circle can be rotated.
+
+#[test]
+fn test_gfa_path_circular_junction() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_circular.gfa")
+        .arg("1+,2+,1+")
+        .arg("--circular");
+
+    cmd.assert().stdout(predicate::str::contains(
+        ">1+,2+
+CGTAACAG
+",
+    ));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_circular.gfa")
+        .arg("1+,2+")
+        .arg("--circular")
+        .arg("--start-segment")
+        .arg("2");
+
+    cmd.assert().stdout(predicate::str::contains(
+        ">1+,2+
+TAACAGCG
+",
+    ));
+
+    // only on the reverse strand
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_circular.gfa")
+        .arg("1+,2+")
+        .arg("--circular")
+        .arg("--start-motif")
+        .arg("ctgt");
+
+    cmd.assert().stdout(predicate::str::contains(
+        ">1+,2+:reverse_complement
+CTGTTACG
+",
+    ));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_circular.gfa")
+        .arg("1+,2+")
+        .arg("--circular")
+        .arg("--start-motif")
+        .arg("GGGG");
+
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("not in the path"));
+
+    Ok(())
+}
+
 // add a quick test for this, as this was broken before.
 // a single segment (given it's in the GFA), will print the
 // segment in full.
@@ -431,3 +620,652 @@ AATCAAGGT
 
     Ok(())
 }
+
+// every path between two oriented segments, and only as many as asked for
+
+#[test]
+fn test_gfa_path_between() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_path_all.gfa")
+        .arg("--between")
+        .arg("11+,13+");
+    cmd.assert()
+        .success()
+        .stdout(">11+,13+\nACCTTGATT\n>11+,12-,13+\nACCTTGATT\n");
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_path_all.gfa")
+        .arg("--between")
+        .arg("11+,13+")
+        .arg("--max-paths")
+        .arg("1");
+    cmd.assert().success().stdout(">11+,13+\nACCTTGATT\n");
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_path_all.gfa")
+        .arg("--between")
+        .arg("13+,11+");
+    cmd.assert()
+        .success()
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "There is no path from 13+ to 11+.",
+        ));
+
+    Ok(())
+}
+
+// test `gfatk SSC`
+// segments 2 and 3 form a cycle, entered from 1 and left to 4.
+// segment 5 is linked to itself.
+
+#[test]
+fn test_gfa_ssc_tsv() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_scc.gfa")
+        .arg("-s")
+        .arg("1")
+        .arg("-f")
+        .arg("tsv");
+
+    cmd.assert()
+        .stdout(predicate::str::contains("2,3\t2\t2\t12\t1+\t4+\tfalse"))
+        .stdout(predicate::str::contains("5\t1\t1\t6\t-\t-\ttrue"));
+
+    Ok(())
+}
+
+#[test]
+fn test_gfa_ssc_threads() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_scc.gfa")
+        .arg("-s")
+        .arg("1")
+        .arg("-t")
+        .arg("2");
+
+    cmd.assert().success().stdout("- 2+\n1+ 4+\n3+ -\n- -\n");
+
+    Ok(())
+}
+
+#[test]
+fn test_gfa_ssc_json() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_scc.gfa")
+        .arg("-s")
+        .arg("2")
+        .arg("-f")
+        .arg("json");
+
+    cmd.assert().stdout(predicate::str::contains(
+        "\"entries\": [\n      \"1+\"\n    ],\n    \"exits\": [\n      \"4+\"\n    ]",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_gfa_ssc_condense() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_scc.gfa")
+        .arg("-c")
+        .arg("gfa");
+
+    cmd.assert()
+        .stdout(predicate::str::contains("S\t2\t*\tLN:i:12\tsg:Z:2,3"))
+        .stdout(predicate::str::contains("L\t1\t+\t2\t+\t*\tec:i:1"));
+
+    Ok(())
+}
+
+#[test]
+fn test_gfa_ssc_outdir() -> Result<(), Box<dyn std::error::Error>> {
+    let outdir = std::env::temp_dir().join(format!("gfatk_ssc_outdir_{}", std::process::id()));
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_scc.gfa")
+        .arg("-s")
+        .arg("2")
+        .arg("-o")
+        .arg(&outdir)
+        .arg("--context")
+        .arg("1");
+
+    cmd.assert().success();
+
+    let manifest = std::fs::read_to_string(outdir.join("manifest.tsv"))?;
+    assert!(manifest.contains("0\tscc_0.gfa\t2,3\t1,4\t12"));
+
+    let subgraph = std::fs::read_to_string(outdir.join("scc_0.gfa"))?;
+    assert!(subgraph.contains("L\t3\t+\t4\t+"));
+    assert!(!subgraph.contains("S\t5"));
+
+    std::fs::remove_dir_all(outdir)?;
+
+    Ok(())
+}
+
+// gzip compressed input, from a file or STDIN
+
+#[test]
+fn test_gfa_gzip_input() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("fasta").arg("./tests/test_linear.gfa.gz");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(">11\nACCTT"));
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("fasta")
+        .write_stdin(std::fs::read("./tests/test_linear.gfa.gz")?);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(">11\nACCTT"));
+
+    Ok(())
+}
+
+// segment names need not be integers
+
+#[test]
+fn test_gfa_string_segment_names() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("linear").arg("./tests/test_names.gfa");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "path=utg000001l+,utg000002l-,utg000003l+",
+    ));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_names.gfa")
+        .arg("utg000001l+,utg000002l-");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("ACCTTGA"));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("extract")
+        .arg("./tests/test_names.gfa")
+        .arg("-s")
+        .arg("utg000003l");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "L\tutg000001l\t+\tutg000002l\t-\t4M",
+        ))
+        .stdout(predicate::str::contains(
+            "P\tpath1\tutg000001l+,utg000002l-,utg000003l+",
+        ));
+
+    // paths are cut down to the segments extracted
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("extract")
+        .arg("./tests/test_path_all.gfa")
+        .arg("-s")
+        .arg("11")
+        .arg("-i")
+        .arg("0");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("P\t14\t11+\t*\n"))
+        .stdout(predicate::str::contains("P\t15\t11-\t*\n"));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_names.gfa")
+        .arg("utg000009l+");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("utg000009l does not exist"));
+
+    Ok(())
+}
+
+// `gfatk rename` writes a mapping, which restores the original names
+
+#[test]
+fn test_gfa_rename_round_trip() -> Result<(), Box<dyn std::error::Error>> {
+    let mapping = std::env::temp_dir().join(format!("gfatk_rename_{}.tsv", std::process::id()));
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("rename")
+        .arg("./tests/test_names.gfa")
+        .arg("-m")
+        .arg(&mapping);
+    let renamed = cmd.assert().success().get_output().stdout.clone();
+    assert!(String::from_utf8(renamed.clone())?.contains("P\tpath1\t0+,1-,2+"));
+
+    let contents = std::fs::read_to_string(&mapping)?;
+    assert!(contents.contains("utg000002l\t1\n"));
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("rename")
+        .arg("-r")
+        .arg("-m")
+        .arg(&mapping)
+        .write_stdin(renamed);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "L\tutg000001l\t+\tutg000002l\t-\t4M",
+    ));
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("rename")
+        .arg("-r")
+        .arg("-m")
+        .arg(&mapping)
+        .write_stdin(">gfatk_linear:path=0+,1-,2+:coverage=2\nACCTTGATT\n");
+    cmd.assert().success().stdout(predicate::str::contains(
+        ">gfatk_linear:path=utg000001l+,utg000002l-,utg000003l+:coverage=2\n",
+    ));
+
+    std::fs::remove_file(mapping)?;
+
+    Ok(())
+}
+
+// walk (W) and jump (J) lines are kept
+
+#[test]
+fn test_gfa_walks() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("extract")
+        .arg("./tests/test_walks.gfa")
+        .arg("-s")
+        .arg("s5")
+        .arg("-i")
+        .arg("1");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("W\tHG003\t1\tchr2\t*\t*\t>s5>s5"))
+        .stdout(predicate::str::contains("chr1").not());
+
+    // walks are cut down to the segments extracted, so the output can be read back in
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("extract")
+        .arg("./tests/test_walks.gfa")
+        .arg("-s")
+        .arg("s2")
+        .arg("-i")
+        .arg("0");
+    let extracted = cmd.assert().success().get_output().stdout.clone();
+    let extracted = String::from_utf8(extracted)?;
+    assert!(extracted.contains("W\tHG002\t1\tchr1\t6\t12\t>s2\n"));
+    assert_eq!(
+        extracted.matches("W\tHG002\t2\tchr1\t*\t*\t>s2\n").count(),
+        2
+    );
+    assert!(!extracted.contains("s1"));
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("stats").write_stdin(extracted);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Number of nodes/segments: 1"));
+
+    let outdir = std::env::temp_dir().join(format!("gfatk_walks_outdir_{}", std::process::id()));
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_walks.gfa")
+        .arg("-s")
+        .arg("2")
+        .arg("-o")
+        .arg(&outdir);
+    cmd.assert().success();
+
+    let subgraph = std::fs::read_to_string(outdir.join("scc_0.gfa"))?;
+    assert!(subgraph.contains("W\tHG002\t1\tchr1\t6\t18\t>s2>s3\n"));
+    assert!(subgraph.contains("W\tHG003\t1\tchr1\t*\t*\t<s3<s2\n"));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("stats").arg(outdir.join("scc_0.gfa"));
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("Number of nodes/segments: 2"));
+
+    std::fs::remove_dir_all(outdir)?;
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_walks.gfa")
+        .arg("-s")
+        .arg("2")
+        .arg("-f")
+        .arg("tsv");
+    cmd.assert().success().stdout(predicate::str::contains(
+        "\tHG002#1#chr1,HG002#2#chr1,HG003#1#chr1\n",
+    ));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("rename").arg("./tests/test_walks.gfa");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "W\tHG003\t1\tchr1\t*\t*\t<3<2<1<0",
+        ))
+        .stdout(predicate::str::contains("J\t3\t+\t4\t+\t100"));
+
+    Ok(())
+}
+
+// test `gfatk index` and `gfatk fasta --segments`
+
+#[test]
+fn test_gfa_index() -> Result<(), Box<dyn std::error::Error>> {
+    // the index is written next to the GFA, so work on a copy
+    let gfa = std::env::temp_dir().join(format!("gfatk_index_{}.gfa", std::process::id()));
+    std::fs::copy("./tests/test_names.gfa", &gfa)?;
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("index").arg(&gfa);
+    cmd.assert().success();
+
+    let index = std::fs::read_to_string(format!("{}.gfai", gfa.display()))?;
+    assert!(index.starts_with("utg000001l\t5\t"));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("fasta")
+        .arg(&gfa)
+        .arg("--segments")
+        .arg("utg000003l,utg000001l");
+    cmd.assert()
+        .success()
+        .stdout(">utg000003l\nCTTGATT\n>utg000001l\nACCTT\n");
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("fasta").arg(&gfa).arg("-s").arg("utg000009l");
+    cmd.assert().failure();
+
+    std::fs::remove_file(format!("{}.gfai", gfa.display()))?;
+    std::fs::remove_file(gfa)?;
+
+    Ok(())
+}
+
+// test the logging options
+
+#[test]
+fn test_gfa_logging() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC").arg("./tests/test_scc.gfa").arg("-q");
+    cmd.assert().success().stderr("");
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC")
+        .arg("./tests/test_scc.gfa")
+        .arg("--log-format")
+        .arg("json");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::starts_with("{\"time\":"))
+        .stderr(predicate::str::contains(
+            "\"level\":\"INFO\",\"elapsed_seconds\":",
+        ))
+        .stderr(predicate::str::contains(
+            "\"message\":\"Found 4 strongly connected components.\"",
+        ));
+
+    Ok(())
+}
+
+// each class of error exits with its own code
+
+#[test]
+fn test_gfa_exit_codes() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("SSC");
+    cmd.assert()
+        .code(2)
+        .stderr(predicate::str::contains("[!]\tNo input GFA."));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("stats").arg("./tests/does_not_exist.gfa");
+    cmd.assert().code(3);
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("stats")
+        .write_stdin("H\tVN:Z:1.0\nS\t1\tACGT\nL\t1\t?\t1\t+\t0M\n");
+    cmd.assert()
+        .code(4)
+        .stderr(predicate::str::contains("Line 3 of the GFA"));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_names.gfa")
+        .arg("utg000009l+");
+    cmd.assert().code(5);
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("stats")
+        .write_stdin("H\tVN:Z:1.0\nS\t1\tACGT\nL\t1\t+\t2\t+\t0M\n");
+    cmd.assert().code(5).stderr(predicate::str::contains(
+        "Link 1+ -> 2+ uses a segment which is not in the GFA: Segment 2 does not exist in the GFA.",
+    ));
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("path")
+        .arg("./tests/test_names.gfa")
+        .arg("utg000003l+,utg000001l+");
+    cmd.assert().code(6).stderr(predicate::str::contains(
+        "There is no link utg000003l+ -> utg000001l+ in the GFA.",
+    ));
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("overlap")
+        .write_stdin("H\tVN:Z:1.0\nS\t1\tACGT\nS\t2\tACGT\nL\t1\t+\t2\t+\t2Q\n");
+    cmd.assert().code(7);
+
+    // the average coverage is not a number without any coverage
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("stats").write_stdin("H\tVN:Z:1.0\nS\t1\tACGT\n");
+    cmd.assert().code(8).stderr(predicate::str::contains(
+        "Missing ec/ll/dp/SC/RC/KC tag: no segment has a coverage tag.",
+    ));
+
+    Ok(())
+}
+
+// test `gfatk validate`
+
+#[test]
+fn test_gfa_validate() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+
+    cmd.arg("validate").arg("./tests/test_walks.gfa");
+    cmd.assert()
+        .success()
+        .stdout("line\tseverity\tproblem\tmessage\n");
+
+    let mut cmd = assert_cmd::Command::cargo_bin("gfatk")?;
+
+    cmd.arg("validate")
+        .write_stdin("S\t1\tACGT\nS\t2\t*\nL\t1\t+\t3\t+\t0M\nP\tp1\t1+,2+\t*\n");
+    cmd.assert()
+        .code(9)
+        .stdout(predicate::str::contains(
+            "2\twarning\tmissing_sequence\tSegment 2 has no sequence.",
+        ))
+        .stdout(predicate::str::contains(
+            "3\terror\tdangling_link\tLink 1+ -> 3+ uses segment 3, which has no S line.",
+        ))
+        .stdout(predicate::str::contains(
+            "4\terror\tpath_missing_link\tPath p1 steps from 1+ to 2+, but they are not linked.",
+        ))
+        .stderr(predicate::str::contains(
+            "The GFA has 2 errors and 1 warnings.",
+        ));
+
+    Ok(())
+}
+
+// test `gfatk bubbles`
+//
+// 1+ -> 2+/3+ -> 4+ is a simple bubble, and 4+ -> 9+ a superbubble
+// (with the simple bubble 5+ -> 6+/7- -> 8+ inside), or a deletion.
+
+#[test]
+fn test_gfa_bubbles() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("bubbles").arg("./tests/test_bubbles.gfa");
+    cmd.assert().success().stdout(
+        "bubble_index\ttype\tsource\tsink\tsegments\tallele_count\talleles\tallele_lengths
+0\tbubble\t1+\t4+\t2,3\t2\t2+;3+\t4,3
+1\tsuperbubble\t4+\t9+\t5,6,7,8\t3\t-;5+,6+,8+;5+,7-,8+\t0,12,13
+2\tbubble\t5+\t8+\t6,7\t2\t6+;7-\t4,5
+",
+    );
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("bubbles")
+        .arg("./tests/test_bubbles.gfa")
+        .arg("-f")
+        .arg("bed");
+    cmd.assert().success().stdout(predicate::str::starts_with(
+        "2\t0\t4\tbubble_0\n3\t0\t3\tbubble_0\n",
+    ));
+
+    Ok(())
+}
+
+// test `gfatk copy-number`
+//
+// each subgraph has its own baseline (20 and 200), fitted to the segments of
+// at least 1kb. The CN tag on b is replaced, and f has no coverage.
+
+#[test]
+fn test_gfa_copy_number() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("copy-number")
+        .arg("./tests/test_copy_number.gfa")
+        .arg("-f")
+        .arg("tsv");
+    cmd.assert().success().stdout(
+        "segment\tcomponent\tcoverage\tbaseline\tcopy_number\tconfidence
+a\t0\t20\t20\t1\t1.00
+b\t0\t41\t20\t2\t0.90
+c\t0\t58\t20\t3\t0.80
+d\t1\t200\t200\t1\t1.00
+e\t1\t610\t200\t3\t0.90
+",
+    );
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("copy-number").arg("./tests/test_copy_number.gfa");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "S\tb\t*\tLN:i:1500\tdp:f:41\tCN:i:2\n",
+        ))
+        .stdout(predicate::str::contains(
+            "S\te\t*\tLN:i:400\tdp:f:610\tCN:i:3\n",
+        ))
+        .stdout(predicate::str::contains("S\tf\t*\tLN:i:100\n"));
+
+    Ok(())
+}
+
+// test `gfatk linear --circular`
+//
+// the repeat r splits the master circle a r b r into two sub-circles, and
+// only with `-i` may r be passed twice.
+
+#[test]
+fn test_gfa_linear_circular() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("linear").arg("-c").arg("./tests/test_isoforms.gfa");
+    cmd.assert().success().stdout(
+        ">gfatk_circular:isoform=1:path=r+,b+:coverage=23
+CGTGTTTT
+>gfatk_circular:isoform=2:path=a+,r+:coverage=19
+AAAACGTG
+",
+    );
+
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("linear")
+        .arg("-c")
+        .arg("-i")
+        .arg("./tests/test_isoforms.gfa");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::starts_with(
+            ">gfatk_circular:isoform=1:path=a+,r+,b+,r+:coverage=42\nAAAACGTGTTTTCGTG\n",
+        ))
+        .stdout(predicate::str::contains(">gfatk_circular:isoform=3:"));
+
+    // r b is found after a r, but has the higher coverage, and
+    // a is then in no isoform
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("linear")
+        .arg("-c")
+        .arg("--max-isoforms")
+        .arg("1")
+        .arg("./tests/test_isoforms.gfa");
+    cmd.assert()
+        .success()
+        .stdout(">gfatk_circular:isoform=1:path=r+,b+:coverage=23\nCGTGTTTT\n")
+        .stderr(predicate::str::contains(
+            "1 of 3 segments are not in any circular isoform.",
+        ));
+
+    // b starts in the 1M overlap of r+ -> b+, and isoforms
+    // without it are not rotated
+    let mut cmd = Command::cargo_bin("gfatk")?;
+    cmd.arg("linear")
+        .arg("-c")
+        .arg("--start-segment")
+        .arg("b")
+        .arg("./tests/test_isoforms.gfa");
+    cmd.assert().success().stdout(
+        ">gfatk_circular:isoform=1:path=r+,b+:coverage=23
+GTTTTCGT
+>gfatk_circular:isoform=2:path=a+,r+:coverage=19
+AAAACGTG
+",
+    );
+
+    Ok(())
+}